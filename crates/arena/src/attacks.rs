@@ -0,0 +1,315 @@
+use magician::{get_bishop_attacks, get_rook_attacks};
+
+use crate::{
+    board::Board,
+    game::Game,
+    piece::{Color, Piece, PieceType},
+};
+
+const fn leaper_table(deltas: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let rank = (sq / 8) as i8;
+        let file = (sq % 8) as i8;
+        let mut i = 0;
+        while i < deltas.len() {
+            let r = rank + deltas[i].0;
+            let f = file + deltas[i].1;
+            if r >= 0 && r < 8 && f >= 0 && f < 8 {
+                table[sq] |= 1u64 << (r * 8 + f);
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    table
+}
+
+const KNIGHT_ATTACKS: [u64; 64] = leaper_table(&[
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+]);
+
+const KING_ATTACKS: [u64; 64] = leaper_table(&[
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+]);
+
+const WHITE_PAWN_ATTACKS: [u64; 64] = leaper_table(&[(1, 1), (1, -1)]);
+const BLACK_PAWN_ATTACKS: [u64; 64] = leaper_table(&[(-1, 1), (-1, -1)]);
+
+// Squares strictly between two squares sharing a rank, file or diagonal, and
+// the full line through both of them. Both are empty for unaligned squares.
+const fn ray_tables() -> ([[u64; 64]; 64], [[u64; 64]; 64]) {
+    let mut between = [[0u64; 64]; 64];
+    let mut line = [[0u64; 64]; 64];
+    let dirs: [(i8, i8); 8] = [
+        (1, 0),
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ];
+    let mut a = 0;
+    while a < 64 {
+        let mut d = 0;
+        while d < 8 {
+            let (dr, df) = dirs[d];
+            // Full line through `a` in this direction and its opposite
+            let mut full = 1u64 << a;
+            let mut sign = 0;
+            while sign < 2 {
+                let (sr, sf) = if sign == 0 { (dr, df) } else { (-dr, -df) };
+                let mut r = (a / 8) as i8 + sr;
+                let mut f = (a % 8) as i8 + sf;
+                while r >= 0 && r < 8 && f >= 0 && f < 8 {
+                    full |= 1u64 << (r * 8 + f);
+                    r += sr;
+                    f += sf;
+                }
+                sign += 1;
+            }
+
+            let mut r = (a / 8) as i8 + dr;
+            let mut f = (a % 8) as i8 + df;
+            let mut path = 0u64;
+            while r >= 0 && r < 8 && f >= 0 && f < 8 {
+                let b = (r * 8 + f) as usize;
+                between[a][b] = path;
+                line[a][b] = full;
+                path |= 1u64 << b;
+                r += dr;
+                f += df;
+            }
+            d += 1;
+        }
+        a += 1;
+    }
+    (between, line)
+}
+
+const RAYS: ([[u64; 64]; 64], [[u64; 64]; 64]) = ray_tables();
+static BETWEEN: [[u64; 64]; 64] = RAYS.0;
+static LINE: [[u64; 64]; 64] = RAYS.1;
+
+pub fn knight_attacks(square: u8) -> u64 {
+    KNIGHT_ATTACKS[square as usize]
+}
+
+pub fn king_attacks(square: u8) -> u64 {
+    KING_ATTACKS[square as usize]
+}
+
+// Squares a pawn of `color` standing on `square` attacks
+pub fn pawn_attacks(color: Color, square: u8) -> u64 {
+    match color {
+        Color::White => WHITE_PAWN_ATTACKS[square as usize],
+        Color::Black => BLACK_PAWN_ATTACKS[square as usize],
+    }
+}
+
+pub fn between(a: u8, b: u8) -> u64 {
+    BETWEEN[a as usize][b as usize]
+}
+
+pub fn line(a: u8, b: u8) -> u64 {
+    LINE[a as usize][b as usize]
+}
+
+impl Game {
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> u64 {
+        self.board(Piece::new(piece_type, color)).0
+    }
+
+    pub fn color_pieces(&self, color: Color) -> u64 {
+        match color {
+            Color::White => {
+                self.white_pawns.0
+                    | self.white_knights.0
+                    | self.white_bishops.0
+                    | self.white_rooks.0
+                    | self.white_queens.0
+                    | self.white_king.0
+            }
+            Color::Black => {
+                self.black_pawns.0
+                    | self.black_knights.0
+                    | self.black_bishops.0
+                    | self.black_rooks.0
+                    | self.black_queens.0
+                    | self.black_king.0
+            }
+        }
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<u8> {
+        let king = self.pieces(PieceType::King, color);
+        if king == 0 {
+            None
+        } else {
+            Some(king.trailing_zeros() as u8)
+        }
+    }
+
+    // All pieces of both colors attacking `square`, with sliders looking through
+    // whatever `occupancy` leaves empty
+    pub fn attackers_to(&self, square: u8, occupancy: u64) -> Board {
+        let rooks = self.white_rooks.0 | self.black_rooks.0;
+        let bishops = self.white_bishops.0 | self.black_bishops.0;
+        let queens = self.white_queens.0 | self.black_queens.0;
+        let knights = self.white_knights.0 | self.black_knights.0;
+        let kings = self.white_king.0 | self.black_king.0;
+
+        Board(
+            (knight_attacks(square) & knights)
+                | (king_attacks(square) & kings)
+                | (pawn_attacks(Color::Black, square) & self.white_pawns.0)
+                | (pawn_attacks(Color::White, square) & self.black_pawns.0)
+                | (get_rook_attacks(square, occupancy) & (rooks | queens))
+                | (get_bishop_attacks(square, occupancy) & (bishops | queens)),
+        )
+    }
+
+    pub fn is_square_attacked(&self, square: u8, by: Color) -> bool {
+        self.attackers_to(square, self.all_pieces().0).0 & self.color_pieces(by) != 0
+    }
+
+    // Enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> Board {
+        self.checkers
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        self.checkers.0 != 0
+    }
+
+    // Pieces of `color` that are pinned to their own king
+    pub fn pinned(&self, color: Color) -> Board {
+        self.pinned[color as usize]
+    }
+
+    pub(crate) fn update_check_info(&mut self) {
+        // Without a royal king nothing is ever in check or pinned
+        if !self.variant.has_royal_king() {
+            self.checkers = Board(0);
+            self.pinned = [Board(0); 2];
+            return;
+        }
+        let us = self.side();
+        let occupancy = self.all_pieces().0;
+        self.checkers = match self.king_square(us) {
+            Some(king) => {
+                Board(self.attackers_to(king, occupancy).0 & self.color_pieces(us.opposite()))
+            }
+            None => Board(0),
+        };
+        self.pinned = [
+            Board(self.compute_pinned(Color::White)),
+            Board(self.compute_pinned(Color::Black)),
+        ];
+    }
+
+    fn compute_pinned(&self, color: Color) -> u64 {
+        let Some(king) = self.king_square(color) else {
+            return 0;
+        };
+        let them = color.opposite();
+        let queens = self.pieces(PieceType::Queen, them);
+        let snipers = (get_rook_attacks(king, 0) & (self.pieces(PieceType::Rook, them) | queens))
+            | (get_bishop_attacks(king, 0) & (self.pieces(PieceType::Bishop, them) | queens));
+
+        let occupancy = self.all_pieces().0;
+        let ours = self.color_pieces(color);
+        let mut pinned = 0;
+        for sniper in Board(snipers).squares() {
+            let blockers = between(king, sniper) & occupancy;
+            if blockers.count_ones() == 1 && blockers & ours != 0 {
+                pinned |= blockers;
+            }
+        }
+        pinned
+    }
+}
+
+#[cfg(test)]
+mod attacks_test {
+    use super::*;
+    use crate::board::square;
+
+    fn bb(names: &[&str]) -> u64 {
+        names.iter().fold(0, |acc, name| acc | 1 << square(name))
+    }
+
+    #[test]
+    fn test_leaper_tables() {
+        assert_eq!(knight_attacks(square("a1")), bb(&["b3", "c2"]));
+        assert_eq!(knight_attacks(square("e4")).count_ones(), 8);
+        assert_eq!(king_attacks(square("h8")), bb(&["g8", "g7", "h7"]));
+        assert_eq!(pawn_attacks(Color::White, square("e4")), bb(&["d5", "f5"]));
+        assert_eq!(pawn_attacks(Color::Black, square("a7")), bb(&["b6"]));
+        assert_eq!(pawn_attacks(Color::White, square("e8")), 0);
+    }
+
+    #[test]
+    fn test_between_and_line() {
+        assert_eq!(between(square("a1"), square("d4")), bb(&["b2", "c3"]));
+        assert_eq!(between(square("e1"), square("e4")), bb(&["e2", "e3"]));
+        assert_eq!(between(square("e1"), square("e2")), 0);
+        assert_eq!(between(square("a1"), square("b3")), 0);
+        assert_eq!(line(square("a1"), square("c3")).count_ones(), 8);
+        assert_eq!(line(square("a1"), square("b3")), 0);
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let game = Game::from_fen("4k3/8/1n6/3p4/4P3/2P5/1B6/R3K2R w KQ - 0 1").expect("valid FEN");
+        let occupancy = game.all_pieces().0;
+
+        // e4 pawn takes d5, knight on b6 defends it
+        let attackers = game.attackers_to(square("d5"), occupancy);
+        assert_eq!(attackers.0, bb(&["e4", "b6"]));
+
+        // Rook a1 and king e1 both see d1
+        let attackers = game.attackers_to(square("d1"), occupancy);
+        assert_eq!(attackers.0, bb(&["a1", "e1"]));
+
+        // The bishop only reaches e5 once c3 is taken out of the occupancy
+        assert_eq!(game.attackers_to(square("e5"), occupancy).0, 0);
+        let attackers = game.attackers_to(square("e5"), occupancy & !bb(&["c3"]));
+        assert_eq!(attackers.0, bb(&["b2"]));
+    }
+
+    #[test]
+    fn test_checkers_and_pins() {
+        let game = Game::from_fen("3rk3/8/8/b7/8/2N5/3K4/8 w - - 0 1").expect("valid FEN");
+        assert!(game.is_in_check());
+        assert_eq!(game.checkers().0, bb(&["d8"]));
+        assert_eq!(game.pinned(Color::White).0, bb(&["c3"]));
+
+        // Two of our pieces on the line means neither is pinned
+        let game = Game::from_fen("4k3/8/8/b7/1P6/2N5/3K4/8 w - - 0 1").expect("valid FEN");
+        assert!(!game.is_in_check());
+        assert_eq!(game.pinned(Color::White).0, 0);
+
+        // Pins are tracked for the side not to move as well
+        let game = Game::from_fen("4k3/4n3/8/8/8/8/8/4RK2 w - - 0 1").expect("valid FEN");
+        assert_eq!(game.pinned(Color::Black).0, bb(&["e7"]));
+        assert_eq!(game.pinned(Color::White).0, 0);
+    }
+}
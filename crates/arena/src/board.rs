@@ -1,6 +1,39 @@
+// Light and dark squares, for telling bishops apart
+pub const LIGHT_SQUARES: u64 = 0x55AA_55AA_55AA_55AA;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Board(pub(crate) u64);
 
 impl Board {
+    pub fn new(bits: u64) -> Self {
+        Board(bits)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Iterates over the indices of set bits, lowest first
+    pub fn squares(self) -> impl Iterator<Item = u8> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let sq = bits.trailing_zeros() as u8;
+            bits &= bits - 1;
+            Some(sq)
+        })
+    }
+
     pub fn has_bit(&self, idx: u8) -> bool {
         if idx >= 64 {
             return false;
@@ -11,4 +44,101 @@ impl Board {
     pub fn set_bit(&mut self, idx: usize) {
         self.0 |= 1 << idx;
     }
+
+    pub fn clear_bit(&mut self, idx: usize) {
+        self.0 &= !(1 << idx);
+    }
+
+    // Mirrors ranks: a1 <-> a8, e2 <-> e7
+    pub fn flipped(self) -> Board {
+        Board(self.0.swap_bytes())
+    }
+
+    // Mirrors files: a1 <-> h1, d4 <-> e4
+    pub fn mirrored(self) -> Board {
+        const K1: u64 = 0x5555_5555_5555_5555;
+        const K2: u64 = 0x3333_3333_3333_3333;
+        const K4: u64 = 0x0F0F_0F0F_0F0F_0F0F;
+        let mut x = self.0;
+        x = ((x >> 1) & K1) | ((x & K1) << 1);
+        x = ((x >> 2) & K2) | ((x & K2) << 2);
+        x = ((x >> 4) & K4) | ((x & K4) << 4);
+        Board(x)
+    }
+}
+
+pub fn square_name(idx: u8) -> String {
+    let file = (b'a' + idx % 8) as char;
+    let rank = (b'1' + idx / 8) as char;
+    format!("{file}{rank}")
+}
+
+pub const fn parse_square(notation: &str) -> Option<u8> {
+    let bytes = notation.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let (file, rank) = (bytes[0], bytes[1]);
+    if file < b'a' || file > b'h' || rank < b'1' || rank > b'8' {
+        return None;
+    }
+    Some((rank - b'1') * 8 + (file - b'a'))
+}
+
+// Index of a square known to be valid, e.g. `square("e4")`. Panics otherwise.
+pub const fn square(notation: &str) -> u8 {
+    match parse_square(notation) {
+        Some(sq) => sq,
+        None => panic!("invalid square"),
+    }
+}
+
+#[cfg(test)]
+mod board_test {
+    use super::*;
+
+    #[test]
+    fn test_flipped_and_mirrored() {
+        let mut b = Board::default();
+        b.set_bit(0); // a1
+        b.set_bit(12); // e2
+
+        let flipped = b.flipped();
+        assert!(flipped.has_bit(56)); // a8
+        assert!(flipped.has_bit(52)); // e7
+        assert_eq!(flipped.0.count_ones(), 2);
+
+        let mirrored = b.mirrored();
+        assert!(mirrored.has_bit(7)); // h1
+        assert!(mirrored.has_bit(11)); // d2
+        assert_eq!(mirrored.0.count_ones(), 2);
+
+        assert_eq!(b.flipped().flipped(), b);
+        assert_eq!(b.mirrored().mirrored(), b);
+    }
+
+    #[test]
+    fn test_squares_iter() {
+        let mut b = Board::new(0);
+        b.set_bit(3);
+        b.set_bit(40);
+        b.set_bit(63);
+        assert_eq!(b.squares().collect::<Vec<_>>(), vec![3, 40, 63]);
+        assert_eq!(b.count(), 3);
+
+        b.clear_bit(40);
+        assert_eq!(b.bits(), (1 << 3) | (1 << 63));
+        assert!(Board::default().is_empty());
+    }
+
+    #[test]
+    fn test_square_notation() {
+        assert_eq!(parse_square("a1"), Some(0));
+        assert_eq!(parse_square("e4"), Some(28));
+        assert_eq!(parse_square("h8"), Some(63));
+        assert_eq!(parse_square("i1"), None);
+        assert_eq!(parse_square("e44"), None);
+        assert_eq!(square_name(28), "e4");
+        assert_eq!(square_name(63), "h8");
+    }
 }
@@ -0,0 +1,269 @@
+use crate::{
+    board::square_name,
+    game::{BLACK_KINGSIDE, BLACK_QUEENSIDE, Game, WHITE_KINGSIDE, WHITE_QUEENSIDE},
+    moves::Move,
+    piece::{Color, Piece, PieceType},
+};
+
+pub fn castling_right(color: Color, kingside: bool) -> u8 {
+    match (color, kingside) {
+        (Color::White, true) => WHITE_KINGSIDE,
+        (Color::White, false) => WHITE_QUEENSIDE,
+        (Color::Black, true) => BLACK_KINGSIDE,
+        (Color::Black, false) => BLACK_QUEENSIDE,
+    }
+}
+
+fn back_rank(color: Color) -> u8 {
+    match color {
+        Color::White => 0,
+        Color::Black => 56,
+    }
+}
+
+impl Game {
+    pub fn has_castling_right(&self, color: Color, kingside: bool) -> bool {
+        self.castling_rights & castling_right(color, kingside) != 0
+    }
+
+    pub fn castling_rook_square(&self, color: Color, kingside: bool) -> u8 {
+        let side = if kingside { 0 } else { 1 };
+        back_rank(color) + self.castling_rook_files[color as usize][side]
+    }
+
+    // King and rook destinations, the same in standard chess and Chess960
+    pub fn castling_destinations(color: Color, kingside: bool) -> (u8, u8) {
+        let base = back_rank(color);
+        if kingside {
+            (base + 6, base + 5)
+        } else {
+            (base + 2, base + 3)
+        }
+    }
+
+    // Rights that disappear once a piece leaves or lands on `square`
+    pub(crate) fn castling_loss(&self, square: u8) -> u8 {
+        let mut lost = 0;
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let right = castling_right(color, kingside);
+                if self.castling_rights & right != 0
+                    && self.castling_rook_square(color, kingside) == square
+                {
+                    lost |= right;
+                }
+            }
+        }
+        lost
+    }
+
+    // UCI notation, castling is written as king-takes-rook in Chess960
+    pub fn move_to_uci(&self, mv: Move) -> String {
+        if self.chess960 && mv.is_castle() {
+            let color = if mv.from() < 8 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let kingside = mv.to() % 8 == 6;
+            let rook = self.castling_rook_square(color, kingside);
+            return format!("{}{}", square_name(mv.from()), square_name(rook));
+        }
+        mv.to_string()
+    }
+
+    // Chess960 start position by its Scharnagl index, 518 being the standard setup
+    pub fn chess960_start_position(index: u16) -> Game {
+        assert!(index < 960, "Chess960 index out of range: {index}");
+        let mut back_rank: [Option<PieceType>; 8] = [None; 8];
+        let mut n = index as usize;
+
+        // Light-squared bishop on b, d, f or h, dark-squared one on a, c, e or g
+        back_rank[n % 4 * 2 + 1] = Some(PieceType::Bishop);
+        n /= 4;
+        back_rank[n % 4 * 2] = Some(PieceType::Bishop);
+        n /= 4;
+
+        let nth_empty = |rank: &[Option<PieceType>; 8], nth: usize| {
+            rank.iter()
+                .enumerate()
+                .filter(|(_, p)| p.is_none())
+                .nth(nth)
+                .map(|(file, _)| file)
+                .unwrap()
+        };
+
+        let queen = nth_empty(&back_rank, n % 6);
+        back_rank[queen] = Some(PieceType::Queen);
+        n /= 6;
+
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        let (first, second) = KNIGHTS[n];
+        let first = nth_empty(&back_rank, first);
+        back_rank[first] = Some(PieceType::Knight);
+        // Placing the first knight shifts the remaining empty squares down by one
+        let second = nth_empty(&back_rank, second - 1);
+        back_rank[second] = Some(PieceType::Knight);
+
+        // Rook, king, rook fill what is left from the a-file side
+        let mut rook_files = Vec::new();
+        for piece_type in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+            let file = nth_empty(&back_rank, 0);
+            back_rank[file] = Some(piece_type);
+            if piece_type == PieceType::Rook {
+                rook_files.push(file as u8);
+            }
+        }
+
+        let mut game = Game::empty();
+        for (file, piece_type) in back_rank.iter().enumerate() {
+            let piece_type = piece_type.unwrap();
+            game.put_piece(Piece::new(piece_type, Color::White), file as u8);
+            game.put_piece(Piece::new(piece_type, Color::Black), 56 + file as u8);
+            game.put_piece(Piece::new(PieceType::Pawn, Color::White), 8 + file as u8);
+            game.put_piece(Piece::new(PieceType::Pawn, Color::Black), 48 + file as u8);
+        }
+        game.castling_rights = WHITE_KINGSIDE | WHITE_QUEENSIDE | BLACK_KINGSIDE | BLACK_QUEENSIDE;
+        game.castling_rook_files = [[rook_files[1], rook_files[0]]; 2];
+        game.chess960 = true;
+        game.refresh_derived();
+        game
+    }
+}
+
+#[cfg(test)]
+mod castling_test {
+    use super::*;
+    use crate::{
+        board::square,
+        fen::START_FEN,
+        moves::{Move, MoveFlag},
+    };
+
+    fn back_rank_of(game: &Game) -> String {
+        (0..8)
+            .map(|file| game.get_piece_at(file).unwrap().to_char())
+            .collect()
+    }
+
+    #[test]
+    fn test_start_positions() {
+        let standard = Game::chess960_start_position(518);
+        assert_eq!(back_rank_of(&standard), "RNBQKBNR");
+        assert_eq!(standard.to_fen(), START_FEN);
+
+        assert_eq!(back_rank_of(&Game::chess960_start_position(0)), "BBQNNRKR");
+        assert_eq!(
+            back_rank_of(&Game::chess960_start_position(959)),
+            "RKRNNQBB"
+        );
+
+        let mut seen = std::collections::HashSet::new();
+        for index in 0..960 {
+            let game = Game::chess960_start_position(index);
+            let rank = back_rank_of(&game);
+            let king = rank.find('K').unwrap();
+            let rooks: Vec<usize> = rank.match_indices('R').map(|(i, _)| i).collect();
+            assert!(rooks[0] < king && king < rooks[1], "{index}: {rank}");
+            let bishops: Vec<usize> = rank.match_indices('B').map(|(i, _)| i).collect();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2, "{index}: {rank}");
+            assert!(seen.insert(rank));
+        }
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // King on b1 with rooks on a1 and c1
+        let fen = "rkr5/8/8/8/8/8/8/RKR5 w KQkq - 0 1";
+        let mut game = Game::from_fen(fen).expect("valid FEN");
+        assert!(game.chess960);
+        assert_eq!(game.castling_rook_square(Color::White, true), square("c1"));
+        assert_eq!(game.castling_rook_square(Color::White, false), square("a1"));
+
+        let castle = Move::new(square("b1"), square("g1"), MoveFlag::KingCastle);
+        assert_eq!(game.move_to_uci(castle), "b1c1");
+        game.make_move(castle);
+        assert_eq!(game.to_fen(), "rkr5/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+
+        // Queenside castle where king and rook swap squares
+        let fen = "2rk4/8/8/8/8/8/8/2RK4 b Qq - 0 1";
+        let mut game = Game::from_fen(fen).expect("valid FEN");
+        let castle = Move::new(square("d8"), square("c8"), MoveFlag::QueenCastle);
+        assert_eq!(game.move_to_uci(castle), "d8c8");
+        game.make_move(castle);
+        assert_eq!(game.to_fen(), "2kr4/8/8/8/8/8/8/2RK4 w Q - 1 2");
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_shredder_fen() {
+        // Inner rook on b1, so X-FEN needs the file letter for it
+        let game = Game::from_fen("rr2k2r/8/8/8/8/8/8/RR2K2R w BHbh - 0 1").expect("valid FEN");
+        assert!(game.chess960);
+        assert_eq!(game.castling_rook_square(Color::White, false), square("b1"));
+        assert_eq!(game.to_fen(), "rr2k2r/8/8/8/8/8/8/RR2K2R w KBkb - 0 1");
+        // No rook on the h-file to castle with
+        assert!(Game::from_fen("rr2k3/8/8/8/8/8/8/RR2K3 w BHbh - 0 1").is_err());
+
+        let game = Game::from_fen("r1r1k2r/8/8/8/8/8/8/R1R1K2R w CHch - 0 1").expect("valid FEN");
+        assert!(game.chess960);
+        assert_eq!(game.castling_rook_square(Color::White, false), square("c1"));
+        assert_eq!(game.castling_rook_square(Color::Black, true), square("h8"));
+        assert_eq!(game.to_fen(), "r1r1k2r/8/8/8/8/8/8/R1R1K2R w KCkc - 0 1");
+
+        let game = Game::from_fen("rkr5/8/8/8/8/8/8/RKR5 w CAca - 0 1").expect("valid FEN");
+        assert_eq!(game.to_fen(), "rkr5/8/8/8/8/8/8/RKR5 w KQkq - 0 1");
+    }
+
+    #[test]
+    fn test_rook_move_loses_its_right() {
+        let mut game = Game::chess960_start_position(0);
+        // BBQNNRKR: rooks on f1 and h1, king on g1
+        assert_eq!(game.castling_rook_square(Color::White, false), square("f1"));
+        game.make_move(Move::new(square("h2"), square("h4"), MoveFlag::DoublePush));
+        game.make_move(Move::new(square("a7"), square("a5"), MoveFlag::DoublePush));
+        game.make_move(Move::quiet(square("h1"), square("h3")));
+        assert!(!game.has_castling_right(Color::White, true));
+        assert!(game.has_castling_right(Color::White, false));
+        assert!(game.has_castling_right(Color::Black, true));
+    }
+
+    #[test]
+    fn test_mirrored_keeps_chess960_rights() {
+        // BBQNNRKR mirrors to RKRNNQBB, still a 960 setup
+        let game = Game::chess960_start_position(0);
+        let mirrored = game.mirrored();
+        assert_eq!(mirrored.castling_rights, 0b1111);
+        assert_eq!(
+            mirrored.castling_rook_square(Color::White, true),
+            square("c1")
+        );
+        assert_eq!(
+            mirrored.castling_rook_square(Color::White, false),
+            square("a1")
+        );
+        assert_eq!(
+            mirrored.castling_rook_square(Color::Black, true),
+            square("c8")
+        );
+        assert_eq!(
+            mirrored.to_fen(),
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+        );
+        assert!(mirrored.mirrored() == game);
+    }
+}
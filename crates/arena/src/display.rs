@@ -0,0 +1,152 @@
+use std::fmt;
+
+use crate::{game::Game, variant::Variant};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiagramOptions {
+    pub unicode: bool,
+    // Draw the board from black's side
+    pub flipped: bool,
+    // (from, to) squares of the last move, drawn in brackets
+    pub highlight: Option<(u8, u8)>,
+}
+
+impl Game {
+    pub fn diagram(&self, options: DiagramOptions) -> String {
+        let ranks: Vec<u8> = if options.flipped {
+            (0..8).collect()
+        } else {
+            (0..8).rev().collect()
+        };
+        let files: Vec<u8> = if options.flipped {
+            (0..8).rev().collect()
+        } else {
+            (0..8).collect()
+        };
+
+        let mut out = String::new();
+        out.push_str("  +------------------------+\n");
+        for &rank in &ranks {
+            out.push_str(&format!("{} |", rank + 1));
+            for &file in &files {
+                let square = rank * 8 + file;
+                let symbol = match self.get_piece_at(square) {
+                    Some(piece) if options.unicode => piece.to_figurine(),
+                    Some(piece) => piece.to_char(),
+                    None => '.',
+                };
+                let highlighted = options
+                    .highlight
+                    .is_some_and(|(from, to)| square == from || square == to);
+                if highlighted {
+                    out.push_str(&format!("[{symbol}]"));
+                } else {
+                    out.push_str(&format!(" {symbol} "));
+                }
+            }
+            out.push_str("|\n");
+        }
+        out.push_str("  +------------------------+\n");
+        let labels: Vec<String> = files
+            .iter()
+            .map(|&file| ((b'a' + file) as char).to_string())
+            .collect();
+        out.push_str(&format!("    {}\n", labels.join("  ")));
+
+        out.push_str(&format!(
+            "\nSide to move: {}\nCastling: {}\nEn passant: {}\n",
+            if self.side_to_move { "Black" } else { "White" },
+            self.castling_field(),
+            self.en_passant_field(),
+        ));
+        // Whatever else the FEN would carry
+        if self.variant != Variant::Standard {
+            out.push_str(&format!("Variant: {}\n", self.variant));
+        }
+        if self.variant == Variant::ThreeCheck {
+            out.push_str(&format!(
+                "Checks given: +{}+{}\n",
+                self.checks_given[0], self.checks_given[1]
+            ));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.diagram(DiagramOptions::default()))
+    }
+}
+
+impl fmt::Debug for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.to_fen())?;
+        write!(f, "{}", self.diagram(DiagramOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod display_test {
+    use super::*;
+    use crate::board::square;
+
+    #[test]
+    fn test_start_position_diagram() {
+        let game = Game::new();
+        let expected = "  +------------------------+
+8 | r  n  b  q  k  b  n  r |
+7 | p  p  p  p  p  p  p  p |
+6 | .  .  .  .  .  .  .  . |
+5 | .  .  .  .  .  .  .  . |
+4 | .  .  .  .  .  .  .  . |
+3 | .  .  .  .  .  .  .  . |
+2 | P  P  P  P  P  P  P  P |
+1 | R  N  B  Q  K  B  N  R |
+  +------------------------+
+    a  b  c  d  e  f  g  h
+
+Side to move: White
+Castling: KQkq
+En passant: -
+";
+        assert_eq!(game.to_string(), expected);
+    }
+
+    #[test]
+    fn test_flipped_unicode_highlighted() {
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").expect("valid FEN");
+        let options = DiagramOptions {
+            unicode: true,
+            flipped: true,
+            highlight: Some((square("d7"), square("d5"))),
+        };
+        let diagram = game.diagram(options);
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines[1], "1 | .  .  .  ♔  .  .  .  . |");
+        assert_eq!(lines[5], "5 | .  .  .  ♙ [♟] .  .  . |");
+        assert_eq!(lines[7], "7 | .  .  .  . [.] .  .  . |");
+        assert_eq!(lines[10], "    h  g  f  e  d  c  b  a");
+        assert!(diagram.contains("Castling: -"));
+        assert!(diagram.contains("En passant: d6"));
+    }
+
+    #[test]
+    fn test_variant_lines() {
+        assert!(!Game::new().to_string().contains("Variant"));
+
+        let game = Game::new_variant(Variant::Antichess);
+        assert!(
+            game.to_string()
+                .ends_with("En passant: -\nVariant: antichess\n")
+        );
+
+        let game =
+            Game::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1", Variant::ThreeCheck)
+                .unwrap();
+        assert!(
+            game.to_string()
+                .ends_with("Variant: 3check\nChecks given: +2+1\n")
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use std::fmt;
+
+use crate::{
+    board::{parse_square, square_name},
+    castling::castling_right,
+    game::Game,
+    piece::{Color, Piece, PieceType},
+    variant::{CHECKS_TO_WIN, Variant},
+};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const BACK_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    MissingField(&'static str),
+    InvalidPlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidCounter(String),
+    InvalidChecks(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing FEN field: {field}"),
+            FenError::InvalidPlacement(s) => write!(f, "invalid piece placement: {s}"),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move: {s}"),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights: {s}"),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square: {s}"),
+            FenError::InvalidCounter(s) => write!(f, "invalid move counter: {s}"),
+            FenError::InvalidChecks(s) => write!(f, "invalid check counter: {s}"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+// Checks given by each side, either as "+1+0" with the checks given so far, the
+// way Stockfish writes it, or as "2+3" with the checks still to give, as lichess
+// does
+fn parse_checks(field: &str) -> Option<[u8; 2]> {
+    let (given, field) = match field.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, field),
+    };
+    let (white, black) = field.split_once('+')?;
+    let count = |n: &str| {
+        let n: u8 = n.parse().ok().filter(|&n| n <= CHECKS_TO_WIN)?;
+        Some(if given { n } else { CHECKS_TO_WIN - n })
+    };
+    Some([count(white)?, count(black)?])
+}
+
+impl Game {
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        Game::from_variant_fen(fen, Variant::Standard)
+    }
+
+    // Reads a position played by the given rules. Kings are only required
+    // where they are royal.
+    pub fn from_variant_fen(fen: &str, variant: Variant) -> Result<Game, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::MissingField("placement"))?;
+        let side = fields
+            .next()
+            .ok_or(FenError::MissingField("side to move"))?;
+        // Castling, en passant and counters are commonly left out of EPD-style strings
+        let castling = fields.next().unwrap_or("-");
+        let en_passant = fields.next().unwrap_or("-");
+        // Three-check counters may sit before or after the move counters
+        let (checks, counters): (Vec<&str>, Vec<&str>) = fields.partition(|f| f.contains('+'));
+        let mut counters = counters.into_iter();
+        let halfmove = counters.next().unwrap_or("0");
+        let fullmove = counters.next().unwrap_or("1");
+
+        let mut game = Game::empty();
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement(placement.to_string()));
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0usize;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    file += skip as usize;
+                } else {
+                    let piece = Piece::from_char(c)
+                        .ok_or_else(|| FenError::InvalidPlacement(placement.to_string()))?;
+                    if file >= 8 {
+                        return Err(FenError::InvalidPlacement(placement.to_string()));
+                    }
+                    game.board_mut(piece).set_bit(rank * 8 + file);
+                    file += 1;
+                }
+            }
+            if file != 8 {
+                return Err(FenError::InvalidPlacement(placement.to_string()));
+            }
+        }
+        // No pawns on the back ranks, and one king a side
+        if (game.white_pawns.0 | game.black_pawns.0) & BACK_RANKS != 0
+            || variant.has_royal_king()
+                && (game.white_king.0.count_ones() != 1 || game.black_king.0.count_ones() != 1)
+        {
+            return Err(FenError::InvalidPlacement(placement.to_string()));
+        }
+
+        game.side_to_move = match side {
+            "w" => false,
+            "b" => true,
+            _ => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+
+        if castling != "-" {
+            game.parse_castling(castling)
+                .ok_or_else(|| FenError::InvalidCastling(castling.to_string()))?;
+        }
+
+        if en_passant != "-" {
+            // The square the opponent's pawn just skipped: rank 6 with white to
+            // move, rank 3 with black
+            let ep_rank = if game.side_to_move { 2 } else { 5 };
+            let sq = parse_square(en_passant)
+                .filter(|sq| sq / 8 == ep_rank)
+                .ok_or_else(|| FenError::InvalidEnPassant(en_passant.to_string()))?;
+            game.en_passant = Some(sq);
+        }
+
+        game.halfmove_clock = halfmove
+            .parse()
+            .map_err(|_| FenError::InvalidCounter(halfmove.to_string()))?;
+        game.fullmove_number = fullmove
+            .parse()
+            .map_err(|_| FenError::InvalidCounter(fullmove.to_string()))?;
+        if let Some(&field) = checks.first() {
+            game.checks_given =
+                parse_checks(field).ok_or_else(|| FenError::InvalidChecks(field.to_string()))?;
+        }
+
+        Ok(game.with_variant(variant))
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.get_piece_at(rank * 8 + file) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.side_to_move { " b " } else { " w " });
+
+        fen.push_str(&self.castling_field());
+        fen.push(' ');
+        fen.push_str(&self.en_passant_field());
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        if self.variant == Variant::ThreeCheck {
+            fen.push_str(&format!(
+                " +{}+{}",
+                self.checks_given[0], self.checks_given[1]
+            ));
+        }
+        fen
+    }
+
+    // Accepts standard KQkq, X-FEN and Shredder-FEN file letters. Rights that
+    // don't fit the standard setup switch the game to Chess960.
+    fn parse_castling(&mut self, castling: &str) -> Option<()> {
+        let mut file_letters = false;
+        for c in castling.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let rank = if color == Color::White { 0 } else { 56 };
+            let king = self.king_square(color).filter(|&k| k / 8 == rank / 8)?;
+            let king_file = king % 8;
+            let rooks = self.pieces(PieceType::Rook, color);
+            let has_rook = |file: u8| rooks & (1 << (rank + file)) != 0;
+
+            let (rook_file, kingside) = match c.to_ascii_lowercase() {
+                'k' => ((king_file + 1..8).rev().find(|&f| has_rook(f))?, true),
+                'q' => ((0..king_file).find(|&f| has_rook(f))?, false),
+                f @ 'a'..='h' => {
+                    let file = f as u8 - b'a';
+                    if !has_rook(file) || file == king_file {
+                        return None;
+                    }
+                    file_letters = true;
+                    (file, file > king_file)
+                }
+                _ => return None,
+            };
+
+            self.castling_rights |= castling_right(color, kingside);
+            self.castling_rook_files[color as usize][if kingside { 0 } else { 1 }] = rook_file;
+            if king_file != 4 || rook_file != if kingside { 7 } else { 0 } {
+                self.chess960 = true;
+            }
+        }
+        if file_letters {
+            self.chess960 = true;
+        }
+        Some(())
+    }
+
+    // X-FEN: KQkq for the outermost rook on each side, the rook's file otherwise
+    pub(crate) fn castling_field(&self) -> String {
+        if self.castling_rights == 0 {
+            return "-".to_string();
+        }
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                if !self.has_castling_right(color, kingside) {
+                    continue;
+                }
+                let rook = self.castling_rook_square(color, kingside);
+                let rank = rook - rook % 8;
+                let outer = if kingside {
+                    rook + 1..rank + 8
+                } else {
+                    rank..rook
+                };
+                let outermost = self.pieces(PieceType::Rook, color)
+                    & outer.fold(0u64, |acc, sq| acc | 1 << sq)
+                    == 0;
+                let c = match (outermost, kingside) {
+                    (true, true) => 'K',
+                    (true, false) => 'Q',
+                    (false, _) => (b'A' + rook % 8) as char,
+                };
+                field.push(if color == Color::White {
+                    c
+                } else {
+                    c.to_ascii_lowercase()
+                });
+            }
+        }
+        field
+    }
+
+    pub(crate) fn en_passant_field(&self) -> String {
+        match self.en_passant {
+            Some(sq) => square_name(sq),
+            None => "-".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod fen_test {
+    use super::*;
+    use crate::{
+        board::square,
+        game::{BLACK_QUEENSIDE, WHITE_KINGSIDE},
+    };
+
+    #[test]
+    fn test_start_position_round_trip() {
+        let game = Game::from_fen(START_FEN).expect("valid FEN");
+        assert!(game == Game::new());
+        assert_eq!(game.to_fen(), START_FEN);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let fen = "rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w Kq c6 0 3";
+        let game = Game::from_fen(fen).expect("valid FEN");
+        assert_eq!(game.castling_rights, WHITE_KINGSIDE | BLACK_QUEENSIDE);
+        assert_eq!(game.en_passant, parse_square("c6"));
+        assert_eq!(game.fullmove_number, 3);
+        assert_eq!(game.side(), Color::White);
+
+        let pawn = game.get_piece_at(square("d5")).unwrap();
+        assert_eq!(pawn.piece_type(), PieceType::Pawn);
+        assert_eq!(pawn.color(), Color::White);
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_kings_only_needed_where_royal() {
+        let fen = "8/8/8/8/8/8/1b6/B7 w - - 0 1";
+        assert!(matches!(
+            Game::from_fen(fen),
+            Err(FenError::InvalidPlacement(_))
+        ));
+        let game = Game::from_variant_fen(fen, Variant::Antichess).unwrap();
+        assert_eq!(game.variant, Variant::Antichess);
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_invalid_fen() {
+        assert!(matches!(
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"),
+            Err(FenError::InvalidPlacement(_))
+        ));
+        assert!(matches!(
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"),
+            Err(FenError::InvalidSideToMove(_))
+        ));
+        assert!(matches!(
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1"),
+            Err(FenError::InvalidCastling(_))
+        ));
+        assert!(matches!(
+            Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +4+0"),
+            Err(FenError::InvalidChecks(_))
+        ));
+        assert!(matches!(Game::from_fen(""), Err(FenError::MissingField(_))));
+
+        // Pawns on a back rank
+        for fen in [
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/p3K3 b - - 0 1",
+        ] {
+            assert!(
+                matches!(Game::from_fen(fen), Err(FenError::InvalidPlacement(_))),
+                "{fen}"
+            );
+        }
+        // A side without a king, or with two
+        for fen in [
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        ] {
+            assert!(
+                matches!(Game::from_fen(fen), Err(FenError::InvalidPlacement(_))),
+                "{fen}"
+            );
+        }
+        // En passant squares on the wrong rank for the side to move
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e6 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 2",
+        ] {
+            assert!(
+                matches!(Game::from_fen(fen), Err(FenError::InvalidEnPassant(_))),
+                "{fen}"
+            );
+        }
+    }
+}
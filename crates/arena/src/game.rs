@@ -2,9 +2,18 @@
 
 use crate::{
     board::Board,
+    make::Undo,
     piece::{Color, Piece, PieceType},
+    psqt::IncrementalEval,
+    variant::Variant,
 };
 
+pub const WHITE_KINGSIDE: u8 = 0b0001;
+pub const WHITE_QUEENSIDE: u8 = 0b0010;
+pub const BLACK_KINGSIDE: u8 = 0b0100;
+pub const BLACK_QUEENSIDE: u8 = 0b1000;
+
+#[derive(Clone)]
 pub struct Game {
     pub white_pawns: Board,
     pub white_rooks: Board,
@@ -21,16 +30,30 @@ pub struct Game {
     pub black_king: Board,
 
     pub castling_rights: u8, // 4 bits: WK, WQ, BK, BQ
-    pub side_to_move: bool,  // false = white, true = black
+    // Back rank file of the rook each right refers to, [color][kingside, queenside]
+    pub castling_rook_files: [[u8; 2]; 2],
+    pub chess960: bool,
+    pub variant: Variant,
+    pub side_to_move: bool, // false = white, true = black
+    pub en_passant: Option<u8>,
+    pub halfmove_clock: u16,
+    pub fullmove_number: u16,
+    // Checks given by [white, black], kept for Three-check
+    pub checks_given: [u8; 2],
 
-                             //TODO:
-                             // - Enpassant flag
-                             // Move Counters
+    // Derived from the position, refreshed by update_check_info
+    pub(crate) checkers: Board,
+    pub(crate) pinned: [Board; 2],
+    pub(crate) key: u64,
+    pub(crate) history: Vec<Undo>,
+    // Only present once the evaluator hands over its tables
+    pub(crate) eval: Option<IncrementalEval>,
 }
 
 impl Game {
     pub fn new() -> Self {
-        Game {
+        magician::init_magician();
+        let mut game = Game {
             // White pieces on ranks 1 and 2
             white_pawns: Board(0x0000_0000_0000_FF00),
             white_knights: Board(0x0000_0000_0000_0042),
@@ -47,9 +70,202 @@ impl Game {
             black_queens: Board(0x0800_0000_0000_0000),
             black_king: Board(0x1000_0000_0000_0000),
 
+            castling_rights: WHITE_KINGSIDE | WHITE_QUEENSIDE | BLACK_KINGSIDE | BLACK_QUEENSIDE,
+            castling_rook_files: [[7, 0]; 2],
+            chess960: false,
+            variant: Variant::Standard,
+            side_to_move: false,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            checks_given: [0; 2],
+
+            checkers: Board(0),
+            pinned: [Board(0); 2],
+            key: 0,
+            history: Vec::new(),
+            eval: None,
+        };
+        game.refresh_derived();
+        game
+    }
+
+    pub fn empty() -> Self {
+        magician::init_magician();
+        Game {
+            white_pawns: Board(0),
+            white_rooks: Board(0),
+            white_knights: Board(0),
+            white_bishops: Board(0),
+            white_queens: Board(0),
+            white_king: Board(0),
+
+            black_pawns: Board(0),
+            black_rooks: Board(0),
+            black_knights: Board(0),
+            black_bishops: Board(0),
+            black_queens: Board(0),
+            black_king: Board(0),
+
             castling_rights: 0,
-            side_to_move: true,
+            castling_rook_files: [[7, 0]; 2],
+            chess960: false,
+            variant: Variant::Standard,
+            side_to_move: false,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            checks_given: [0; 2],
+
+            checkers: Board(0),
+            pinned: [Board(0); 2],
+            key: 0,
+            history: Vec::new(),
+            eval: None,
+        }
+    }
+
+    // Recomputes the check info and key after a position was set up directly
+    pub(crate) fn refresh_derived(&mut self) {
+        self.update_check_info();
+        self.key = self.compute_key();
+    }
+
+    pub fn side(&self) -> Color {
+        if self.side_to_move {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+
+    pub fn board(&self, piece: Piece) -> &Board {
+        match (piece.color(), piece.piece_type()) {
+            (Color::White, PieceType::Pawn) => &self.white_pawns,
+            (Color::White, PieceType::Knight) => &self.white_knights,
+            (Color::White, PieceType::Bishop) => &self.white_bishops,
+            (Color::White, PieceType::Rook) => &self.white_rooks,
+            (Color::White, PieceType::Queen) => &self.white_queens,
+            (Color::White, PieceType::King) => &self.white_king,
+            (Color::Black, PieceType::Pawn) => &self.black_pawns,
+            (Color::Black, PieceType::Knight) => &self.black_knights,
+            (Color::Black, PieceType::Bishop) => &self.black_bishops,
+            (Color::Black, PieceType::Rook) => &self.black_rooks,
+            (Color::Black, PieceType::Queen) => &self.black_queens,
+            (Color::Black, PieceType::King) => &self.black_king,
+        }
+    }
+
+    pub fn board_mut(&mut self, piece: Piece) -> &mut Board {
+        match (piece.color(), piece.piece_type()) {
+            (Color::White, PieceType::Pawn) => &mut self.white_pawns,
+            (Color::White, PieceType::Knight) => &mut self.white_knights,
+            (Color::White, PieceType::Bishop) => &mut self.white_bishops,
+            (Color::White, PieceType::Rook) => &mut self.white_rooks,
+            (Color::White, PieceType::Queen) => &mut self.white_queens,
+            (Color::White, PieceType::King) => &mut self.white_king,
+            (Color::Black, PieceType::Pawn) => &mut self.black_pawns,
+            (Color::Black, PieceType::Knight) => &mut self.black_knights,
+            (Color::Black, PieceType::Bishop) => &mut self.black_bishops,
+            (Color::Black, PieceType::Rook) => &mut self.black_rooks,
+            (Color::Black, PieceType::Queen) => &mut self.black_queens,
+            (Color::Black, PieceType::King) => &mut self.black_king,
+        }
+    }
+
+    // Swaps colors and mirrors ranks, so white's position becomes black's and vice versa
+    pub fn flipped(&self) -> Game {
+        let rights = self.castling_rights;
+        let mut game = Game {
+            white_pawns: self.black_pawns.flipped(),
+            white_rooks: self.black_rooks.flipped(),
+            white_knights: self.black_knights.flipped(),
+            white_bishops: self.black_bishops.flipped(),
+            white_queens: self.black_queens.flipped(),
+            white_king: self.black_king.flipped(),
+
+            black_pawns: self.white_pawns.flipped(),
+            black_rooks: self.white_rooks.flipped(),
+            black_knights: self.white_knights.flipped(),
+            black_bishops: self.white_bishops.flipped(),
+            black_queens: self.white_queens.flipped(),
+            black_king: self.white_king.flipped(),
+
+            castling_rights: ((rights & 0b0011) << 2) | ((rights & 0b1100) >> 2),
+            castling_rook_files: [self.castling_rook_files[1], self.castling_rook_files[0]],
+            chess960: self.chess960,
+            variant: self.variant,
+            side_to_move: !self.side_to_move,
+            en_passant: self.en_passant.map(|sq| sq ^ 56),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            checks_given: [self.checks_given[1], self.checks_given[0]],
+
+            checkers: Board(0),
+            pinned: [Board(0); 2],
+            key: 0,
+            history: Vec::new(),
+            eval: None,
+        };
+        game.refresh_derived();
+        if let Some(tables) = self.eval_tables() {
+            game.set_eval_tables(tables.clone());
+        }
+        game
+    }
+
+    // Mirrors files. Castling rights are dropped since kings and rooks no longer
+    // stand where castling expects them.
+    pub fn mirrored(&self) -> Game {
+        // Kingside and queenside trade places. A standard game ends up with its
+        // king on the d-file, where it can't castle by standard rules; a
+        // Chess960 setup stays one.
+        let (castling_rights, castling_rook_files) = if self.chess960 {
+            let rights = self.castling_rights;
+            (
+                (rights & 0b0101) << 1 | (rights & 0b1010) >> 1,
+                self.castling_rook_files
+                    .map(|[kingside, queenside]| [7 - queenside, 7 - kingside]),
+            )
+        } else {
+            (0, [[7, 0]; 2])
+        };
+        let mut game = Game {
+            white_pawns: self.white_pawns.mirrored(),
+            white_rooks: self.white_rooks.mirrored(),
+            white_knights: self.white_knights.mirrored(),
+            white_bishops: self.white_bishops.mirrored(),
+            white_queens: self.white_queens.mirrored(),
+            white_king: self.white_king.mirrored(),
+
+            black_pawns: self.black_pawns.mirrored(),
+            black_rooks: self.black_rooks.mirrored(),
+            black_knights: self.black_knights.mirrored(),
+            black_bishops: self.black_bishops.mirrored(),
+            black_queens: self.black_queens.mirrored(),
+            black_king: self.black_king.mirrored(),
+
+            castling_rights,
+            castling_rook_files,
+            chess960: self.chess960,
+            variant: self.variant,
+            side_to_move: self.side_to_move,
+            en_passant: self.en_passant.map(|sq| sq ^ 7),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            checks_given: self.checks_given,
+
+            checkers: Board(0),
+            pinned: [Board(0); 2],
+            key: 0,
+            history: Vec::new(),
+            eval: None,
+        };
+        game.refresh_derived();
+        if let Some(tables) = self.eval_tables() {
+            game.set_eval_tables(tables.clone());
         }
+        game
     }
 
     pub fn all_pieces(&self) -> Board {
@@ -127,10 +343,50 @@ impl Game {
     }
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Two games are equal when they describe the same position; move history and
+// the cached check, key and evaluation state are left out
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.white_pawns == other.white_pawns
+            && self.white_rooks == other.white_rooks
+            && self.white_knights == other.white_knights
+            && self.white_bishops == other.white_bishops
+            && self.white_queens == other.white_queens
+            && self.white_king == other.white_king
+            && self.black_pawns == other.black_pawns
+            && self.black_rooks == other.black_rooks
+            && self.black_knights == other.black_knights
+            && self.black_bishops == other.black_bishops
+            && self.black_queens == other.black_queens
+            && self.black_king == other.black_king
+            && self.castling_rights == other.castling_rights
+            && self.castling_rook_files == other.castling_rook_files
+            && self.chess960 == other.chess960
+            && self.side_to_move == other.side_to_move
+            && self.en_passant == other.en_passant
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_number == other.fullmove_number
+            && self.variant == other.variant
+            && self.checks_given == other.checks_given
+    }
+}
+
+impl Eq for Game {}
+
 #[cfg(test)]
 mod game_test {
     use super::*;
-    use crate::piece::{Color, PieceType};
+    use crate::{
+        board::square,
+        moves::{Move, MoveFlag},
+        piece::{Color, PieceType},
+    };
 
     #[test]
     fn test_initial_positions() {
@@ -163,4 +419,47 @@ mod game_test {
         // Empty square: a4 (index 24)
         assert!(game.get_piece_at(24).is_none());
     }
+
+    #[test]
+    fn test_flipped() {
+        let game = Game::from_fen("rnbqkbnr/pp1ppppp/8/2pP4/8/8/PPP1PPPP/RNBQKBNR w Kq c6 0 3")
+            .expect("valid FEN");
+        let flipped = game.flipped();
+        assert_eq!(
+            flipped.to_fen(),
+            "rnbqkbnr/ppp1pppp/8/8/2Pp4/8/PP1PPPPP/RNBQKBNR b Qk c3 0 3"
+        );
+        assert!(flipped.flipped() == game);
+
+        // The start position is color-symmetric apart from the side to move
+        let start = Game::new().flipped();
+        assert_eq!(
+            start.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/R3K2R w KQ d6 0 1").expect("valid FEN");
+        let mirrored = game.mirrored();
+        assert_eq!(mirrored.to_fen(), "3k4/8/8/3Pp3/8/8/8/R2K3R w - e6 0 1");
+        assert_eq!(
+            mirrored.mirrored().to_fen(),
+            "4k3/8/8/3pP3/8/8/8/R3K2R w - d6 0 1"
+        );
+    }
+
+    #[test]
+    fn test_equality_ignores_history() {
+        let mut played = Game::new();
+        played.make_move(Move::new(square("e2"), square("e4"), MoveFlag::DoublePush));
+        let parsed = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+            .expect("valid FEN");
+        assert!(played == parsed);
+
+        let mut other = parsed.clone();
+        other.halfmove_clock = 1;
+        assert!(other != parsed);
+    }
 }
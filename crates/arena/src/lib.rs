@@ -1,6 +1,17 @@
+pub mod attacks;
 pub mod board;
+pub mod castling;
+pub mod display;
+pub mod fen;
 pub mod game;
+pub mod make;
+pub mod moves;
 pub mod piece;
+pub mod psqt;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod variant;
+pub mod zobrist;
 
 #[cfg(test)]
 mod tests {}
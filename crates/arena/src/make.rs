@@ -0,0 +1,306 @@
+use crate::{
+    board::Board,
+    castling::castling_right,
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::{Color, Piece, PieceType},
+    variant::Variant,
+    zobrist::{castling_key, checks_key, piece_key, side_key},
+};
+
+// Everything make_move overwrites that can't be recomputed when taking the move back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Undo {
+    pub(crate) mv: Move,
+    pub(crate) captured: Option<Piece>,
+    pub(crate) castling_rights: u8,
+    pub(crate) en_passant: Option<u8>,
+    pub(crate) halfmove_clock: u16,
+    pub(crate) checks_given: [u8; 2],
+    pub(crate) checkers: Board,
+    pub(crate) pinned: [Board; 2],
+    pub(crate) key: u64,
+}
+
+impl Game {
+    pub(crate) fn put_piece(&mut self, piece: Piece, square: u8) {
+        self.board_mut(piece).set_bit(square as usize);
+        self.key ^= piece_key(piece, square);
+        if let Some(eval) = &mut self.eval {
+            eval.add(piece, square);
+        }
+    }
+
+    pub(crate) fn remove_piece(&mut self, piece: Piece, square: u8) {
+        self.board_mut(piece).clear_bit(square as usize);
+        self.key ^= piece_key(piece, square);
+        if let Some(eval) = &mut self.eval {
+            eval.remove(piece, square);
+        }
+    }
+
+    // Plays `mv` without checking it is legal. The move must at least be
+    // pseudo-legal, i.e. a piece of the side to move stands on its origin, and
+    // only promote to a king in Antichess.
+    pub fn make_move(&mut self, mv: Move) {
+        assert!(
+            mv.promotion_piece() != Some(PieceType::King) || self.variant == Variant::Antichess,
+            "make_move: king promotion outside Antichess"
+        );
+        let us = self.side();
+        let from = mv.from();
+        let to = mv.to();
+        let piece = self
+            .get_piece_at(from)
+            .expect("make_move: no piece on origin square");
+
+        let captured_square = if mv.is_en_passant() {
+            match us {
+                Color::White => to - 8,
+                Color::Black => to + 8,
+            }
+        } else {
+            to
+        };
+        let captured = if mv.is_capture() {
+            self.get_piece_at(captured_square)
+        } else {
+            None
+        };
+
+        self.history.push(Undo {
+            mv,
+            captured,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            checks_given: self.checks_given,
+            checkers: self.checkers,
+            pinned: self.pinned,
+            key: self.key,
+        });
+        // Take out the state terms now, they go back in once the move is done
+        self.key ^= castling_key(self.castling_rights) ^ self.en_passant_key();
+
+        if let Some(captured) = captured {
+            self.remove_piece(captured, captured_square);
+        }
+
+        self.remove_piece(piece, from);
+        match mv.promotion_piece() {
+            Some(promoted) => self.put_piece(Piece::new(promoted, us), to),
+            None => self.put_piece(piece, to),
+        }
+
+        if mv.is_castle() {
+            let kingside = mv.flag() == MoveFlag::KingCastle;
+            let rook_from = self.castling_rook_square(us, kingside);
+            let (_, rook_to) = Game::castling_destinations(us, kingside);
+            let rook = Piece::new(PieceType::Rook, us);
+            self.remove_piece(rook, rook_from);
+            self.put_piece(rook, rook_to);
+        }
+
+        let mut lost = self.castling_loss(from) | self.castling_loss(to);
+        if piece.piece_type() == PieceType::King {
+            lost |= castling_right(us, true) | castling_right(us, false);
+        }
+        self.castling_rights &= !lost;
+        self.en_passant = if mv.is_double_push() {
+            Some((from + to) / 2)
+        } else {
+            None
+        };
+
+        if piece.piece_type() == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if us == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = !self.side_to_move;
+        self.key ^= castling_key(self.castling_rights) ^ self.en_passant_key() ^ side_key();
+
+        self.update_check_info();
+        if self.variant == Variant::ThreeCheck && self.checkers.0 != 0 {
+            let given = &mut self.checks_given[us as usize];
+            self.key ^= checks_key(us, *given);
+            *given = given.saturating_add(1);
+            self.key ^= checks_key(us, *given);
+        }
+    }
+
+    // Takes back the last move played with make_move and returns it
+    pub fn unmake_move(&mut self) -> Move {
+        let undo = self
+            .history
+            .pop()
+            .expect("unmake_move: no move to take back");
+        let mv = undo.mv;
+
+        self.side_to_move = !self.side_to_move;
+        let us = self.side();
+        if us == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        let from = mv.from();
+        let to = mv.to();
+        let moved = self
+            .get_piece_at(to)
+            .expect("unmake_move: no piece on destination square");
+        self.remove_piece(moved, to);
+        if mv.is_promotion() {
+            self.put_piece(Piece::new(PieceType::Pawn, us), from);
+        } else {
+            self.put_piece(moved, from);
+        }
+
+        if mv.is_castle() {
+            let kingside = mv.flag() == MoveFlag::KingCastle;
+            let rook_from = self.castling_rook_square(us, kingside);
+            let (_, rook_to) = Game::castling_destinations(us, kingside);
+            let rook = Piece::new(PieceType::Rook, us);
+            self.remove_piece(rook, rook_to);
+            self.put_piece(rook, rook_from);
+        }
+
+        if let Some(captured) = undo.captured {
+            let captured_square = if mv.is_en_passant() {
+                match us {
+                    Color::White => to - 8,
+                    Color::Black => to + 8,
+                }
+            } else {
+                to
+            };
+            self.put_piece(captured, captured_square);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.checks_given = undo.checks_given;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+        self.key = undo.key;
+        mv
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last().map(|undo| undo.mv)
+    }
+}
+
+#[cfg(test)]
+mod make_test {
+    use super::*;
+    use crate::{
+        board::square,
+        fen::START_FEN,
+        game::{BLACK_KINGSIDE, WHITE_KINGSIDE, WHITE_QUEENSIDE},
+    };
+
+    #[test]
+    fn test_make_unmake_round_trip() {
+        let mut game = Game::new();
+        let moves = [
+            Move::new(square("e2"), square("e4"), MoveFlag::DoublePush),
+            Move::new(square("d7"), square("d5"), MoveFlag::DoublePush),
+            Move::capture(square("e4"), square("d5")),
+            Move::quiet(square("g8"), square("f6")),
+        ];
+        for mv in moves {
+            game.make_move(mv);
+        }
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkb1r/ppp1pppp/5n2/3P4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 1 3"
+        );
+        assert_eq!(game.last_move(), Some(moves[3]));
+
+        for mv in moves.iter().rev() {
+            assert_eq!(game.unmake_move(), *mv);
+        }
+        assert_eq!(game.to_fen(), START_FEN);
+        assert_eq!(game.last_move(), None);
+    }
+
+    #[test]
+    fn test_en_passant_and_promotion() {
+        let mut game = Game::from_fen("8/1P2k3/8/3pP3/8/8/8/4K3 w - d6 0 1").expect("valid FEN");
+        let fen = game.to_fen();
+
+        game.make_move(Move::new(square("e5"), square("d6"), MoveFlag::EnPassant));
+        assert_eq!(game.to_fen(), "8/1P2k3/3P4/8/8/8/8/4K3 b - - 0 1");
+        assert!(game.is_in_check());
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+
+        game.make_move(Move::promotion(
+            square("b7"),
+            square("b8"),
+            PieceType::Knight,
+            false,
+        ));
+        assert_eq!(game.to_fen(), "1N6/4k3/8/3pP3/8/8/8/4K3 b - - 0 1");
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10";
+        let mut game = Game::from_fen(fen).expect("valid FEN");
+
+        game.make_move(Move::new(square("e1"), square("g1"), MoveFlag::KingCastle));
+        assert_eq!(game.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 4 10");
+
+        game.make_move(Move::new(square("e8"), square("c8"), MoveFlag::QueenCastle));
+        assert_eq!(game.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 5 11");
+
+        game.unmake_move();
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+
+        // Capturing a rook on its home square removes that right
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/6B1/R3K2R w KQkq - 0 1").expect("valid FEN");
+        game.make_move(Move::capture(square("g2"), square("a8")));
+        assert_eq!(
+            game.castling_rights,
+            WHITE_KINGSIDE | WHITE_QUEENSIDE | BLACK_KINGSIDE
+        );
+    }
+
+    #[test]
+    fn test_check_info_follows_moves() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1").expect("valid FEN");
+        assert!(!game.is_in_check());
+
+        game.make_move(Move::quiet(square("d2"), square("e2")));
+        assert!(game.is_in_check());
+        assert_eq!(game.checkers().bits(), 1 << square("e2"));
+
+        game.make_move(Move::quiet(square("e8"), square("d8")));
+        assert!(!game.is_in_check());
+
+        game.unmake_move();
+        assert!(game.is_in_check());
+        game.unmake_move();
+        assert!(!game.is_in_check());
+    }
+
+    #[test]
+    #[should_panic(expected = "king promotion outside Antichess")]
+    fn test_king_promotion_needs_antichess() {
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move(Move::promotion(
+            square("b7"),
+            square("b8"),
+            PieceType::King,
+            false,
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+
+use crate::{board::square_name, piece::PieceType};
+
+// Layout: bits 0-5 from square, bits 6-11 to square, bits 12-15 flag
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Move(u16);
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveFlag {
+    Quiet = 0,
+    DoublePush = 1,
+    KingCastle = 2,
+    QueenCastle = 3,
+    Capture = 4,
+    EnPassant = 5,
+    // Antichess only, the king being an ordinary piece there
+    KingPromotion = 6,
+    KingPromotionCapture = 7,
+    KnightPromotion = 8,
+    BishopPromotion = 9,
+    RookPromotion = 10,
+    QueenPromotion = 11,
+    KnightPromotionCapture = 12,
+    BishopPromotionCapture = 13,
+    RookPromotionCapture = 14,
+    QueenPromotionCapture = 15,
+}
+
+impl MoveFlag {
+    fn from_bits(bits: u8) -> Self {
+        match bits {
+            0 => MoveFlag::Quiet,
+            1 => MoveFlag::DoublePush,
+            2 => MoveFlag::KingCastle,
+            3 => MoveFlag::QueenCastle,
+            4 => MoveFlag::Capture,
+            5 => MoveFlag::EnPassant,
+            6 => MoveFlag::KingPromotion,
+            7 => MoveFlag::KingPromotionCapture,
+            8 => MoveFlag::KnightPromotion,
+            9 => MoveFlag::BishopPromotion,
+            10 => MoveFlag::RookPromotion,
+            11 => MoveFlag::QueenPromotion,
+            12 => MoveFlag::KnightPromotionCapture,
+            13 => MoveFlag::BishopPromotionCapture,
+            14 => MoveFlag::RookPromotionCapture,
+            15 => MoveFlag::QueenPromotionCapture,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Move {
+    pub const NULL: Move = Move(0);
+
+    pub fn new(from: u8, to: u8, flag: MoveFlag) -> Self {
+        debug_assert!(from < 64 && to < 64);
+        Self(from as u16 | (to as u16) << 6 | (flag as u16) << 12)
+    }
+
+    pub fn quiet(from: u8, to: u8) -> Self {
+        Self::new(from, to, MoveFlag::Quiet)
+    }
+
+    pub fn capture(from: u8, to: u8) -> Self {
+        Self::new(from, to, MoveFlag::Capture)
+    }
+
+    // Panics if `piece_type` is a pawn
+    pub fn promotion(from: u8, to: u8, piece_type: PieceType, capture: bool) -> Self {
+        let flag = match (piece_type, capture) {
+            (PieceType::Knight, false) => MoveFlag::KnightPromotion,
+            (PieceType::Bishop, false) => MoveFlag::BishopPromotion,
+            (PieceType::Rook, false) => MoveFlag::RookPromotion,
+            (PieceType::Queen, false) => MoveFlag::QueenPromotion,
+            (PieceType::Knight, true) => MoveFlag::KnightPromotionCapture,
+            (PieceType::Bishop, true) => MoveFlag::BishopPromotionCapture,
+            (PieceType::Rook, true) => MoveFlag::RookPromotionCapture,
+            (PieceType::Queen, true) => MoveFlag::QueenPromotionCapture,
+            (PieceType::King, false) => MoveFlag::KingPromotion,
+            (PieceType::King, true) => MoveFlag::KingPromotionCapture,
+            _ => panic!("cannot promote to {piece_type:?}"),
+        };
+        Self::new(from, to, flag)
+    }
+
+    pub fn from_raw(raw: u16) -> Self {
+        Self(raw)
+    }
+
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    pub fn from(self) -> u8 {
+        (self.0 & 0x3F) as u8
+    }
+
+    pub fn to(self) -> u8 {
+        ((self.0 >> 6) & 0x3F) as u8
+    }
+
+    pub fn flag(self) -> MoveFlag {
+        MoveFlag::from_bits((self.0 >> 12) as u8)
+    }
+
+    pub fn is_null(self) -> bool {
+        self == Self::NULL
+    }
+
+    pub fn is_capture(self) -> bool {
+        self.0 & (4 << 12) != 0 && self.0 >> 12 != MoveFlag::KingPromotion as u16
+    }
+
+    pub fn is_promotion(self) -> bool {
+        self.0 >> 12 >= MoveFlag::KingPromotion as u16
+    }
+
+    pub fn is_castle(self) -> bool {
+        matches!(self.flag(), MoveFlag::KingCastle | MoveFlag::QueenCastle)
+    }
+
+    pub fn is_en_passant(self) -> bool {
+        self.flag() == MoveFlag::EnPassant
+    }
+
+    pub fn is_double_push(self) -> bool {
+        self.flag() == MoveFlag::DoublePush
+    }
+
+    pub fn promotion_piece(self) -> Option<PieceType> {
+        if !self.is_promotion() {
+            return None;
+        }
+        if self.0 >> 12 < MoveFlag::KnightPromotion as u16 {
+            return Some(PieceType::King);
+        }
+        Some(match (self.0 >> 12) & 0b11 {
+            0 => PieceType::Knight,
+            1 => PieceType::Bishop,
+            2 => PieceType::Rook,
+            _ => PieceType::Queen,
+        })
+    }
+}
+
+// UCI long algebraic notation, e.g. e2e4, e7e8q, 0000 for the null move
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}", square_name(self.from()), square_name(self.to()))?;
+        if let Some(piece_type) = self.promotion_piece() {
+            let c = match piece_type {
+                PieceType::Knight => 'n',
+                PieceType::Bishop => 'b',
+                PieceType::Rook => 'r',
+                PieceType::King => 'k',
+                _ => 'q',
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Move({self}, {:?})", self.flag())
+    }
+}
+
+#[cfg(test)]
+mod moves_test {
+    use super::*;
+    use crate::board::square;
+
+    #[test]
+    fn test_move_packing() {
+        let mv = Move::new(square("e2"), square("e4"), MoveFlag::DoublePush);
+        assert_eq!(mv.from(), square("e2"));
+        assert_eq!(mv.to(), square("e4"));
+        assert_eq!(mv.flag(), MoveFlag::DoublePush);
+        assert!(mv.is_double_push());
+        assert!(!mv.is_capture());
+        assert!(!mv.is_promotion());
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+        assert_eq!(Move::from_raw(mv.raw()), mv);
+
+        let ep = Move::new(square("e5"), square("d6"), MoveFlag::EnPassant);
+        assert!(ep.is_capture());
+        assert!(ep.is_en_passant());
+
+        let castle = Move::new(square("e1"), square("c1"), MoveFlag::QueenCastle);
+        assert!(castle.is_castle());
+        assert!(!castle.is_capture());
+    }
+
+    #[test]
+    fn test_promotions() {
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ] {
+            for capture in [false, true] {
+                let mv = Move::promotion(square("b7"), square("a8"), piece_type, capture);
+                assert!(mv.is_promotion());
+                assert_eq!(mv.is_capture(), capture);
+                assert_eq!(mv.promotion_piece(), Some(piece_type));
+            }
+        }
+        assert_eq!(
+            Move::quiet(square("a2"), square("a3")).promotion_piece(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_null_move() {
+        assert!(Move::NULL.is_null());
+        assert!(Move::default().is_null());
+        assert!(!Move::quiet(square("g1"), square("f3")).is_null());
+        assert!(!Move::from_raw(6 << 12).is_null());
+        assert_eq!(Move::from_raw(6 << 12).flag(), MoveFlag::KingPromotion);
+    }
+
+    #[test]
+    fn test_uci_display() {
+        assert_eq!(Move::quiet(square("g1"), square("f3")).to_string(), "g1f3");
+        assert_eq!(
+            Move::promotion(square("e7"), square("e8"), PieceType::Queen, false).to_string(),
+            "e7e8q"
+        );
+        assert_eq!(
+            Move::promotion(square("b2"), square("a1"), PieceType::Knight, true).to_string(),
+            "b2a1n"
+        );
+        assert_eq!(
+            Move::promotion(square("b2"), square("b1"), PieceType::King, false).to_string(),
+            "b2b1k"
+        );
+        assert_eq!(Move::NULL.to_string(), "0000");
+        assert_eq!(
+            format!("{:?}", Move::capture(square("d4"), square("e5"))),
+            "Move(d4e5, Capture)"
+        );
+    }
+}
@@ -1,12 +1,23 @@
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White = 0,
     Black = 1,
 }
 
+impl Color {
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn = 0,
     Knight = 1,
@@ -51,6 +62,58 @@ impl Piece {
     pub fn is_black(self) -> bool {
         self.color() == Color::Black
     }
+
+    // FEN letter: uppercase for white, lowercase for black
+    pub fn to_char(self) -> char {
+        let c = match self.piece_type() {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+        };
+        if self.is_white() {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+
+    pub fn to_figurine(self) -> char {
+        match (self.color(), self.piece_type()) {
+            (Color::White, PieceType::Pawn) => '♙',
+            (Color::White, PieceType::Knight) => '♘',
+            (Color::White, PieceType::Bishop) => '♗',
+            (Color::White, PieceType::Rook) => '♖',
+            (Color::White, PieceType::Queen) => '♕',
+            (Color::White, PieceType::King) => '♔',
+            (Color::Black, PieceType::Pawn) => '♟',
+            (Color::Black, PieceType::Knight) => '♞',
+            (Color::Black, PieceType::Bishop) => '♝',
+            (Color::Black, PieceType::Rook) => '♜',
+            (Color::Black, PieceType::Queen) => '♛',
+            (Color::Black, PieceType::King) => '♚',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let piece_type = match c.to_ascii_lowercase() {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
+            _ => return None,
+        };
+        Some(Self::new(piece_type, color))
+    }
 }
 
 impl std::fmt::Debug for Piece {
@@ -79,6 +142,9 @@ mod piece_test {
         assert_eq!(p2.color(), Color::White);
         assert!(p2.is_white());
         assert!(!p2.is_black());
+
+        assert_eq!(Color::White.opposite(), Color::Black);
+        assert_eq!(Color::Black.opposite(), Color::White);
     }
 
     #[test]
@@ -95,6 +161,20 @@ mod piece_test {
         assert!(debug_str_black.contains("color: Black"));
     }
 
+    #[test]
+    fn test_piece_chars() {
+        let p = Piece::new(PieceType::Knight, Color::White);
+        assert_eq!(p.to_char(), 'N');
+        assert_eq!(Piece::from_char('N'), Some(p));
+
+        let p = Piece::new(PieceType::Queen, Color::Black);
+        assert_eq!(p.to_char(), 'q');
+        assert_eq!(Piece::from_char('q'), Some(p));
+
+        assert_eq!(Piece::from_char('x'), None);
+        assert_eq!(p.to_figurine(), '♛');
+    }
+
     #[test]
     #[should_panic(expected = "unreachable")]
     fn test_piece_type_unreachable() {
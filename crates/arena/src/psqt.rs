@@ -0,0 +1,265 @@
+use std::{
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    sync::Arc,
+};
+
+use crate::{
+    game::Game,
+    piece::{Color, Piece, PieceType},
+};
+
+// Middlegame / endgame pair, always from white's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Score { mg, eg }
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+    fn add(self, rhs: Score) -> Score {
+        Score::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+    fn sub(self, rhs: Score) -> Score {
+        Score::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+    fn neg(self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Score) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, rhs: Score) {
+        *self = *self - rhs;
+    }
+}
+
+// Tables supplied by the evaluator, indexed by piece type. Piece-square
+// entries are written for white (a1 = 0); black looks them up rank-mirrored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsqtTables {
+    pub material: [Score; 6],
+    pub psqt: [[Score; 64]; 6],
+    pub phase_weights: [i32; 6],
+}
+
+impl PsqtTables {
+    // Phase with the full starting material on the board
+    pub fn max_phase(&self) -> i32 {
+        let w = &self.phase_weights;
+        2 * (8 * w[PieceType::Pawn as usize]
+            + 2 * w[PieceType::Knight as usize]
+            + 2 * w[PieceType::Bishop as usize]
+            + 2 * w[PieceType::Rook as usize]
+            + w[PieceType::Queen as usize]
+            + w[PieceType::King as usize])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IncrementalEval {
+    tables: Arc<PsqtTables>,
+    material: Score,
+    psqt: Score,
+    phase: i32,
+}
+
+impl IncrementalEval {
+    fn new(tables: Arc<PsqtTables>) -> Self {
+        IncrementalEval {
+            tables,
+            material: Score::default(),
+            psqt: Score::default(),
+            phase: 0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, piece: Piece, square: u8) {
+        let (material, psqt, phase) = self.terms(piece, square);
+        self.material += material;
+        self.psqt += psqt;
+        self.phase += phase;
+    }
+
+    pub(crate) fn remove(&mut self, piece: Piece, square: u8) {
+        let (material, psqt, phase) = self.terms(piece, square);
+        self.material -= material;
+        self.psqt -= psqt;
+        self.phase -= phase;
+    }
+
+    fn terms(&self, piece: Piece, square: u8) -> (Score, Score, i32) {
+        let pt = piece.piece_type() as usize;
+        let material = self.tables.material[pt];
+        let phase = self.tables.phase_weights[pt];
+        match piece.color() {
+            Color::White => (material, self.tables.psqt[pt][square as usize], phase),
+            Color::Black => (
+                -material,
+                -self.tables.psqt[pt][(square ^ 56) as usize],
+                phase,
+            ),
+        }
+    }
+}
+
+impl Game {
+    // Starts tracking material, piece-square and phase totals through every
+    // make/unmake. The totals are built from scratch once here.
+    pub fn set_eval_tables(&mut self, tables: Arc<PsqtTables>) {
+        let mut eval = IncrementalEval::new(tables);
+        for sq in self.all_pieces().squares() {
+            eval.add(self.get_piece_at(sq).unwrap(), sq);
+        }
+        self.eval = Some(eval);
+    }
+
+    pub fn clear_eval_tables(&mut self) {
+        self.eval = None;
+    }
+
+    pub fn eval_tables(&self) -> Option<&Arc<PsqtTables>> {
+        self.eval.as_ref().map(|eval| &eval.tables)
+    }
+
+    pub fn material(&self) -> Option<Score> {
+        self.eval.as_ref().map(|eval| eval.material)
+    }
+
+    pub fn psqt_score(&self) -> Option<Score> {
+        self.eval.as_ref().map(|eval| eval.psqt)
+    }
+
+    pub fn phase(&self) -> Option<i32> {
+        self.eval.as_ref().map(|eval| eval.phase)
+    }
+
+    // Material plus piece-square, blended between middlegame and endgame by the
+    // phase, from white's point of view
+    pub fn tapered_score(&self) -> Option<i32> {
+        let eval = self.eval.as_ref()?;
+        let max_phase = eval.tables.max_phase().max(1);
+        let phase = eval.phase.clamp(0, max_phase);
+        let total = eval.material + eval.psqt;
+        Some((total.mg * phase + total.eg * (max_phase - phase)) / max_phase)
+    }
+}
+
+#[cfg(test)]
+mod psqt_test {
+    use super::*;
+    use crate::{
+        board::square,
+        moves::{Move, MoveFlag},
+    };
+
+    fn tables() -> Arc<PsqtTables> {
+        let mut psqt = [[Score::default(); 64]; 6];
+        for (pt, table) in psqt.iter_mut().enumerate() {
+            for (sq, entry) in table.iter_mut().enumerate() {
+                // Arbitrary but asymmetric, so mirroring mistakes show up
+                let rank = (sq / 8) as i32;
+                let file = (sq % 8) as i32;
+                *entry = Score::new(rank * 3 + file + pt as i32, rank * 5 - file * 2);
+            }
+        }
+        Arc::new(PsqtTables {
+            material: [
+                Score::new(82, 94),
+                Score::new(337, 281),
+                Score::new(365, 297),
+                Score::new(477, 512),
+                Score::new(1025, 936),
+                Score::new(0, 0),
+            ],
+            psqt,
+            phase_weights: [0, 1, 1, 2, 4, 0],
+        })
+    }
+
+    fn from_scratch(game: &Game) -> (Score, Score, i32) {
+        let mut fresh = game.clone();
+        fresh.set_eval_tables(tables());
+        (
+            fresh.material().unwrap(),
+            fresh.psqt_score().unwrap(),
+            fresh.phase().unwrap(),
+        )
+    }
+
+    fn incremental(game: &Game) -> (Score, Score, i32) {
+        (
+            game.material().unwrap(),
+            game.psqt_score().unwrap(),
+            game.phase().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_start_position() {
+        let mut game = Game::new();
+        assert_eq!(game.tapered_score(), None);
+
+        game.set_eval_tables(tables());
+        assert_eq!(game.material(), Some(Score::default()));
+        assert_eq!(game.phase(), Some(24));
+        assert_eq!(tables().max_phase(), 24);
+    }
+
+    #[test]
+    fn test_incremental_matches_recompute() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        game.set_eval_tables(tables());
+        let start = incremental(&game);
+
+        let moves = [
+            Move::new(square("e5"), square("d6"), MoveFlag::EnPassant),
+            Move::new(square("e8"), square("g8"), MoveFlag::KingCastle),
+            Move::promotion(square("b7"), square("a8"), PieceType::Queen, true),
+            Move::quiet(square("g8"), square("h7")),
+            Move::new(square("e1"), square("c1"), MoveFlag::QueenCastle),
+        ];
+        for mv in moves {
+            game.make_move(mv);
+            assert_eq!(incremental(&game), from_scratch(&game), "after {mv}");
+        }
+        for _ in moves {
+            game.unmake_move();
+            assert_eq!(incremental(&game), from_scratch(&game));
+        }
+        assert_eq!(incremental(&game), start);
+    }
+
+    #[test]
+    fn test_flipped_negates_score() {
+        let mut game =
+            Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        game.set_eval_tables(tables());
+        let flipped = game.flipped();
+        assert_eq!(flipped.material(), game.material().map(|s| -s));
+        assert_eq!(flipped.psqt_score(), game.psqt_score().map(|s| -s));
+        assert_eq!(flipped.phase(), game.phase());
+        assert_eq!(flipped.tapered_score(), game.tapered_score().map(|s| -s));
+    }
+}
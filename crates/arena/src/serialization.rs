@@ -0,0 +1,318 @@
+// Serde support, enabled with the `serde` feature.
+//
+// `Piece` is written as its FEN letter and `Move` as its squares plus flag.
+// `Game` is written as a FEN string by default, led by tags for whatever the FEN
+// can't carry, e.g. "chess960:" or "antichess:". The `fen` and `structured`
+// modules can be picked per field with `#[serde(with = "...")]`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::{
+    board::{parse_square, square_name},
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::{Color, Piece},
+    variant::Variant,
+};
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.to_char())
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let c = char::deserialize(deserializer)?;
+        Piece::from_char(c).ok_or_else(|| D::Error::custom(format!("invalid piece: {c}")))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoveRepr {
+    from: String,
+    to: String,
+    flag: MoveFlag,
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MoveRepr {
+            from: square_name(self.from()),
+            to: square_name(self.to()),
+            flag: self.flag(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MoveRepr::deserialize(deserializer)?;
+        let from = parse_square(&repr.from)
+            .ok_or_else(|| D::Error::custom(format!("invalid square: {}", repr.from)))?;
+        let to = parse_square(&repr.to)
+            .ok_or_else(|| D::Error::custom(format!("invalid square: {}", repr.to)))?;
+        Ok(Move::new(from, to, repr.flag))
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        tagged::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        tagged::deserialize(deserializer)
+    }
+}
+
+pub mod fen {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(game: &Game, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&game.to_fen())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        Game::from_fen(&fen).map_err(D::Error::custom)
+    }
+}
+
+// The default form: the FEN, preceded by the variant and a "chess960" tag when
+// they apply, each followed by a colon. A plain FEN reads as standard chess.
+pub mod tagged {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(game: &Game, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut text = String::new();
+        if game.variant != Variant::Standard {
+            text.push_str(game.variant.name());
+            text.push(':');
+        }
+        if game.chess960 {
+            text.push_str("chess960:");
+        }
+        text.push_str(&game.to_fen());
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let mut fields: Vec<&str> = text.split(':').collect();
+        let fen = fields.pop().unwrap_or_default();
+        let mut variant = Variant::Standard;
+        let mut chess960 = false;
+        for tag in fields {
+            match tag {
+                "chess960" => chess960 = true,
+                _ => {
+                    variant = Variant::from_name(tag)
+                        .ok_or_else(|| D::Error::custom(format!("unknown tag: {tag}")))?;
+                }
+            }
+        }
+        let mut game = Game::from_variant_fen(fen, variant).map_err(D::Error::custom)?;
+        game.chess960 |= chess960;
+        Ok(game)
+    }
+}
+
+// Field-by-field form which also keeps the Chess960 flag, rook files and variant
+// that a FEN of a standard-looking position can't carry
+pub mod structured {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct GameRepr {
+        pieces: BTreeMap<String, Piece>,
+        side_to_move: Color,
+        castling_rights: u8,
+        castling_rook_files: [[u8; 2]; 2],
+        chess960: bool,
+        #[serde(default)]
+        variant: Variant,
+        en_passant: Option<String>,
+        halfmove_clock: u16,
+        fullmove_number: u16,
+        #[serde(default)]
+        checks_given: [u8; 2],
+    }
+
+    pub fn serialize<S: Serializer>(game: &Game, serializer: S) -> Result<S::Ok, S::Error> {
+        let pieces = game
+            .all_pieces()
+            .squares()
+            .map(|sq| (square_name(sq), game.get_piece_at(sq).unwrap()))
+            .collect();
+        GameRepr {
+            pieces,
+            side_to_move: game.side(),
+            castling_rights: game.castling_rights,
+            castling_rook_files: game.castling_rook_files,
+            chess960: game.chess960,
+            variant: game.variant,
+            en_passant: game.en_passant.map(square_name),
+            halfmove_clock: game.halfmove_clock,
+            fullmove_number: game.fullmove_number,
+            checks_given: game.checks_given,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let repr = GameRepr::deserialize(deserializer)?;
+        let mut game = Game::empty();
+        for (name, piece) in repr.pieces {
+            let sq = parse_square(&name)
+                .ok_or_else(|| D::Error::custom(format!("invalid square: {name}")))?;
+            game.put_piece(piece, sq);
+        }
+        if repr.castling_rights > 0b1111
+            || repr.castling_rook_files.iter().flatten().any(|&f| f > 7)
+        {
+            return Err(D::Error::custom("invalid castling rights"));
+        }
+        game.side_to_move = repr.side_to_move == Color::Black;
+        game.castling_rights = repr.castling_rights;
+        game.castling_rook_files = repr.castling_rook_files;
+        game.chess960 = repr.chess960;
+        game.variant = repr.variant;
+        game.en_passant = match repr.en_passant {
+            Some(name) => Some(
+                parse_square(&name)
+                    .ok_or_else(|| D::Error::custom(format!("invalid square: {name}")))?,
+            ),
+            None => None,
+        };
+        game.halfmove_clock = repr.halfmove_clock;
+        game.fullmove_number = repr.fullmove_number;
+        game.checks_given = repr.checks_given;
+        game.refresh_derived();
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod serialization_test {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        board::square,
+        game::Game,
+        moves::{Move, MoveFlag},
+        piece::{Color, Piece, PieceType},
+        variant::Variant,
+    };
+
+    #[test]
+    fn test_piece_round_trip() {
+        let piece = Piece::new(PieceType::Knight, Color::Black);
+        let json = serde_json::to_string(&piece).unwrap();
+        assert_eq!(json, "\"n\"");
+        assert_eq!(serde_json::from_str::<Piece>(&json).unwrap(), piece);
+        assert!(serde_json::from_str::<Piece>("\"x\"").is_err());
+
+        let json = serde_json::to_string(&(Color::White, PieceType::Queen)).unwrap();
+        assert_eq!(json, "[\"White\",\"Queen\"]");
+        let back: (Color, PieceType) = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, (Color::White, PieceType::Queen));
+    }
+
+    #[test]
+    fn test_move_round_trip() {
+        let mv = Move::new(square("e5"), square("d6"), MoveFlag::EnPassant);
+        let json = serde_json::to_string(&mv).unwrap();
+        assert_eq!(json, r#"{"from":"e5","to":"d6","flag":"EnPassant"}"#);
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), mv);
+        assert!(serde_json::from_str::<Move>(r#"{"from":"z9","to":"d6","flag":"Quiet"}"#).is_err());
+    }
+
+    #[test]
+    fn test_game_fen_round_trip() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let game = Game::from_fen(fen).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(json, format!("\"{fen}\""));
+        let back: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_fen(), fen);
+        assert!(serde_json::from_str::<Game>("\"not a fen\"").is_err());
+    }
+
+    #[test]
+    fn test_game_default_form_is_lossless() {
+        let game = Game::chess960_start_position(518);
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            json,
+            "\"chess960:rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\""
+        );
+        let back: Game = serde_json::from_str(&json).unwrap();
+        assert!(back == game);
+        assert!(back.chess960);
+
+        for variant in Variant::ALL {
+            let game = Game::new_variant(variant);
+            let json = serde_json::to_string(&game).unwrap();
+            let back: Game = serde_json::from_str(&json).unwrap();
+            assert!(back == game, "{json}");
+        }
+        let game =
+            Game::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1", Variant::ThreeCheck)
+                .unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(json, "\"3check:4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1\"");
+        assert!(serde_json::from_str::<Game>(&json).unwrap() == game);
+        // No kings needed when they aren't royal
+        let back: Game = serde_json::from_str("\"antichess:8/8/8/8/8/p7/P7/8 b - - 0 1\"").unwrap();
+        assert_eq!(back.variant, Variant::Antichess);
+        assert!(
+            serde_json::from_str::<Game>("\"crazyhouse:4k3/8/8/8/8/8/8/4K3 w - - 0 1\"").is_err()
+        );
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Record {
+        #[serde(with = "crate::serialization::structured")]
+        game: Game,
+        best: Move,
+    }
+
+    #[test]
+    fn test_game_structured_round_trip() {
+        let game = Game::chess960_start_position(518);
+        let record = Record {
+            game,
+            best: Move::new(square("e2"), square("e4"), MoveFlag::DoublePush),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""e1":"K""#));
+        assert!(json.contains(r#""chess960":true"#));
+
+        let back: Record = serde_json::from_str(&json).unwrap();
+        assert!(back.game == record.game);
+        assert!(back.game.chess960);
+        assert_eq!(back.best, record.best);
+    }
+
+    #[test]
+    fn test_variant_survives_structured_form() {
+        let game =
+            Game::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1", Variant::ThreeCheck)
+                .unwrap();
+        let record = Record {
+            game,
+            best: Move::NULL,
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""variant":"ThreeCheck""#));
+        let back: Record = serde_json::from_str(&json).unwrap();
+        assert!(back.game == record.game);
+        assert_eq!(back.game.checks_given, [2, 1]);
+    }
+}
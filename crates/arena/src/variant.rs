@@ -0,0 +1,194 @@
+use std::fmt;
+
+use crate::{
+    game::Game,
+    piece::{Color, PieceType},
+};
+
+// d4, e4, d5 and e5, the goal in King of the Hill
+pub const HILL: u64 = 0x0000_0018_1800_0000;
+
+// Checks that win a game of Three-check
+pub const CHECKS_TO_WIN: u8 = 3;
+
+// The rules a game is played by. The position only carries what the rules need
+// to be told apart; deciding who won is up to the rule sets built on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    #[default]
+    Standard,
+    // A king reaching the center wins
+    KingOfTheHill,
+    // The third check wins
+    ThreeCheck,
+    // Captures are compulsory, the king is an ordinary piece and losing every
+    // piece wins
+    Antichess,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [
+        Variant::Standard,
+        Variant::KingOfTheHill,
+        Variant::ThreeCheck,
+        Variant::Antichess,
+    ];
+
+    // The name used by the UCI_Variant option and the PGN Variant tag
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "chess",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::ThreeCheck => "3check",
+            Variant::Antichess => "antichess",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name.to_ascii_lowercase().as_str() {
+            "chess" | "standard" => Some(Variant::Standard),
+            "kingofthehill" | "koth" => Some(Variant::KingOfTheHill),
+            "3check" | "threecheck" => Some(Variant::ThreeCheck),
+            "antichess" | "giveaway" => Some(Variant::Antichess),
+            _ => None,
+        }
+    }
+
+    // Whether there is such a thing as check, and the king must be kept out of it
+    pub fn has_royal_king(self) -> bool {
+        self != Variant::Antichess
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Game {
+    // The standard starting position played by the given rules
+    pub fn new_variant(variant: Variant) -> Game {
+        Game::new().with_variant(variant)
+    }
+
+    // The same position under other rules. Antichess has no castling, so the
+    // rights go.
+    pub fn with_variant(mut self, variant: Variant) -> Game {
+        self.variant = variant;
+        if variant == Variant::Antichess {
+            self.castling_rights = 0;
+        }
+        self.refresh_derived();
+        self
+    }
+
+    // Checks `color` has given so far, only counted in Three-check
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks_given[color as usize]
+    }
+
+    // Whether the variant's own goal has been reached, a king on the hill or a
+    // third check. No moves are left to play in such a position.
+    pub fn variant_goal_reached(&self) -> bool {
+        match self.variant {
+            Variant::KingOfTheHill => {
+                (self.pieces(PieceType::King, Color::White)
+                    | self.pieces(PieceType::King, Color::Black))
+                    & HILL
+                    != 0
+            }
+            Variant::ThreeCheck => self.checks_given.iter().any(|&n| n >= CHECKS_TO_WIN),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod variant_test {
+    use super::*;
+    use crate::{
+        board::square,
+        moves::{Move, MoveFlag},
+    };
+
+    #[test]
+    fn test_names() {
+        for variant in Variant::ALL {
+            assert_eq!(Variant::from_name(variant.name()), Some(variant));
+        }
+        assert_eq!(Variant::from_name("KOTH"), Some(Variant::KingOfTheHill));
+        assert_eq!(Variant::from_name("crazyhouse"), None);
+        assert_eq!(Variant::default(), Variant::Standard);
+    }
+
+    #[test]
+    fn test_antichess_has_no_check() {
+        let game = Game::new_variant(Variant::Antichess);
+        assert_eq!(game.castling_rights, 0);
+        // The queen attacks the king, which is no check here
+        let game =
+            Game::from_variant_fen("4k3/8/8/8/8/8/8/4QK2 b - - 0 1", Variant::Antichess).unwrap();
+        assert!(!game.is_in_check());
+    }
+
+    #[test]
+    fn test_three_check_counter() {
+        // 1. e4 e5 2. Bc4 Nc6 3. Bxf7+ Kxf7
+        let mut game = Game::new_variant(Variant::ThreeCheck);
+        let key = game.key();
+        for mv in [
+            Move::new(square("e2"), square("e4"), MoveFlag::DoublePush),
+            Move::new(square("e7"), square("e5"), MoveFlag::DoublePush),
+            Move::quiet(square("f1"), square("c4")),
+            Move::quiet(square("b8"), square("c6")),
+            Move::capture(square("c4"), square("f7")),
+        ] {
+            game.make_move(mv);
+            assert_eq!(game.key(), game.compute_key());
+        }
+        assert_eq!(game.checks_given(Color::White), 1);
+        assert_eq!(game.checks_given(Color::Black), 0);
+        assert!(game.to_fen().ends_with(" +1+0"));
+        assert!(!game.variant_goal_reached());
+
+        // The count is part of the key
+        let reread = Game::from_fen(&game.to_fen())
+            .unwrap()
+            .with_variant(Variant::ThreeCheck);
+        assert_eq!(reread.checks_given(Color::White), 1);
+        assert_eq!(reread.key(), game.key());
+
+        for _ in 0..5 {
+            game.unmake_move();
+        }
+        assert_eq!(game.checks_given(Color::White), 0);
+        assert_eq!(game.key(), key);
+
+        // Standard games don't count
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        game.make_move(Move::quiet(square("a1"), square("a8")));
+        assert_eq!(game.checks_given(Color::White), 0);
+    }
+
+    #[test]
+    fn test_goals() {
+        let game = Game::from_fen("8/8/4k3/8/3K4/8/8/8 b - - 0 1").unwrap();
+        assert!(!game.variant_goal_reached());
+        assert!(
+            game.with_variant(Variant::KingOfTheHill)
+                .variant_goal_reached()
+        );
+
+        // Lichess writes the checks still to give in the middle of the FEN
+        let game = Game::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 3+1 0 1", Variant::ThreeCheck)
+            .unwrap();
+        assert_eq!(game.checks_given(Color::Black), 2);
+        assert!(!game.variant_goal_reached());
+        let game =
+            Game::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +3+0", Variant::ThreeCheck)
+                .unwrap();
+        assert!(game.variant_goal_reached());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    attacks::pawn_attacks,
+    game::Game,
+    piece::{Color, Piece, PieceType},
+};
+
+// Random keys for hashing positions. They are fixed at compile time so a key
+// means the same position across runs and threads.
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+    side: u64,
+    // Three-check counters by color, from one check given up to three or more
+    checks: [[u64; 4]; 2],
+}
+
+// SplitMix64, good enough for hash keys and usable in a const context
+const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> Keys {
+    let mut state = 0x2545_F491_4F6C_DD1D;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 16],
+        en_passant: [0; 8],
+        side: 0,
+        checks: [[0; 4]; 2],
+    };
+    let mut piece = 0;
+    while piece < 12 {
+        let mut sq = 0;
+        while sq < 64 {
+            keys.pieces[piece][sq] = next_key(&mut state);
+            sq += 1;
+        }
+        piece += 1;
+    }
+    // No rights hashes to nothing, so a bare position only depends on pieces
+    let mut rights = 1;
+    while rights < 16 {
+        keys.castling[rights] = next_key(&mut state);
+        rights += 1;
+    }
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant[file] = next_key(&mut state);
+        file += 1;
+    }
+    keys.side = next_key(&mut state);
+    // As with castling, no checks given hashes to nothing
+    let mut color = 0;
+    while color < 2 {
+        let mut count = 1;
+        while count < 4 {
+            keys.checks[color][count] = next_key(&mut state);
+            count += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+static KEYS: Keys = generate_keys();
+
+pub(crate) fn piece_key(piece: Piece, square: u8) -> u64 {
+    let idx = piece.color() as usize * 6 + piece.piece_type() as usize;
+    KEYS.pieces[idx][square as usize]
+}
+
+pub(crate) fn castling_key(rights: u8) -> u64 {
+    KEYS.castling[(rights & 0b1111) as usize]
+}
+
+pub(crate) fn checks_key(color: Color, given: u8) -> u64 {
+    KEYS.checks[color as usize][given.min(3) as usize]
+}
+
+pub(crate) fn side_key() -> u64 {
+    KEYS.side
+}
+
+impl Game {
+    // Zobrist key of the position, kept up to date by make/unmake. Move
+    // counters aren't part of it, so repeated positions share a key.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    // How many times the current position occurred earlier in the moves played
+    // on this game. Only positions since the last capture or pawn move can
+    // match, and only those with the same side to move.
+    pub fn repetitions(&self) -> usize {
+        let reversible = (self.halfmove_clock as usize).min(self.history.len());
+        self.history
+            .iter()
+            .rev()
+            .take(reversible)
+            .skip(1)
+            .step_by(2)
+            .filter(|undo| undo.key == self.key)
+            .count()
+    }
+
+    // The key built from scratch, which the incremental one must always equal
+    pub fn compute_key(&self) -> u64 {
+        let mut key = 0;
+        for sq in self.all_pieces().squares() {
+            key ^= piece_key(self.get_piece_at(sq).unwrap(), sq);
+        }
+        key ^= castling_key(self.castling_rights);
+        key ^= self.en_passant_key();
+        if self.side_to_move {
+            key ^= side_key();
+        }
+        key ^= checks_key(Color::White, self.checks_given[0]);
+        key ^= checks_key(Color::Black, self.checks_given[1]);
+        key
+    }
+
+    // The en passant square only counts when a pawn can actually take there,
+    // otherwise every double push would make the position look new
+    pub(crate) fn en_passant_key(&self) -> u64 {
+        let Some(ep) = self.en_passant else {
+            return 0;
+        };
+        let us = self.side();
+        if pawn_attacks(us.opposite(), ep) & self.pieces(PieceType::Pawn, us) != 0 {
+            KEYS.en_passant[(ep % 8) as usize]
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod zobrist_test {
+    use super::*;
+    use crate::{
+        board::square,
+        moves::{Move, MoveFlag},
+    };
+
+    #[test]
+    fn test_transpositions_share_a_key() {
+        let mut game = Game::new();
+        let start = game.key();
+        assert_eq!(start, game.compute_key());
+        for mv in [
+            Move::quiet(square("g1"), square("f3")),
+            Move::quiet(square("g8"), square("f6")),
+            Move::quiet(square("f3"), square("g1")),
+            Move::quiet(square("f6"), square("g8")),
+        ] {
+            game.make_move(mv);
+            assert_eq!(game.key(), game.compute_key());
+        }
+        assert_eq!(game.key(), start);
+
+        // Moving the king and back loses the castling rights
+        game.make_move(Move::new(square("e2"), square("e4"), MoveFlag::DoublePush));
+        game.make_move(Move::new(square("e7"), square("e5"), MoveFlag::DoublePush));
+        game.make_move(Move::quiet(square("e1"), square("e2")));
+        game.make_move(Move::quiet(square("e8"), square("e7")));
+        game.make_move(Move::quiet(square("e2"), square("e1")));
+        game.make_move(Move::quiet(square("e7"), square("e8")));
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w - - 4 4";
+        assert_eq!(game.key(), Game::from_fen(fen).unwrap().key());
+
+        for _ in 0..10 {
+            game.unmake_move();
+        }
+        assert_eq!(game.key(), start);
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let mut game = Game::new();
+        let shuffle = [
+            Move::quiet(square("g1"), square("f3")),
+            Move::quiet(square("g8"), square("f6")),
+            Move::quiet(square("f3"), square("g1")),
+            Move::quiet(square("f6"), square("g8")),
+        ];
+        assert_eq!(game.repetitions(), 0);
+        for round in 1..=2 {
+            for mv in shuffle {
+                game.make_move(mv);
+            }
+            assert_eq!(game.repetitions(), round);
+        }
+        game.make_move(shuffle[0]);
+        assert_eq!(game.repetitions(), 2);
+        game.unmake_move();
+
+        // A pawn move draws a line under everything before it
+        game.make_move(Move::quiet(square("e2"), square("e3")));
+        game.make_move(shuffle[1]);
+        game.make_move(Move::quiet(square("f1"), square("e2")));
+        game.make_move(shuffle[3]);
+        assert_eq!(game.repetitions(), 0);
+    }
+
+    #[test]
+    fn test_en_passant_only_when_capturable() {
+        let mut game = Game::new();
+        game.make_move(Move::new(square("e2"), square("e4"), MoveFlag::DoublePush));
+        let without_ep =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(game.en_passant, Some(square("e3")));
+        assert_eq!(game.key(), without_ep.key());
+
+        let capturable = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let stale = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(capturable.key(), stale.key());
+    }
+
+    #[test]
+    fn test_incremental_through_special_moves() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let start = game.key();
+        let moves = [
+            Move::new(square("e5"), square("d6"), MoveFlag::EnPassant),
+            Move::new(square("e8"), square("g8"), MoveFlag::KingCastle),
+            Move::promotion(square("b7"), square("a8"), PieceType::Queen, true),
+            Move::quiet(square("g8"), square("h7")),
+            Move::new(square("e1"), square("c1"), MoveFlag::QueenCastle),
+        ];
+        for mv in moves {
+            game.make_move(mv);
+            assert_eq!(game.key(), game.compute_key(), "after {mv}");
+        }
+        for _ in moves {
+            game.unmake_move();
+            assert_eq!(game.key(), game.compute_key());
+        }
+        assert_eq!(game.key(), start);
+        assert_ne!(game.flipped().key(), start);
+    }
+}
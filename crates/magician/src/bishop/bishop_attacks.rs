@@ -34,13 +34,13 @@ mod test_bishop {
     #[test]
     fn test_bishop_attack_generation_single() {
         let blockers = blockers_from_squares(&["c4"]);
-        println!("");
+        println!();
         println!("The Blocker:");
         print_board(blockers);
         let attacks = bishop_attacks_from(notation_to_index("d4"), blockers);
         println!("The Attacks:");
         print_board(attacks);
-        println!("");
+        println!();
     }
 
     #[test]
@@ -48,13 +48,13 @@ mod test_bishop {
         let possible_occupancy = bishop_occupancy_mask(notation_to_index("e4"));
         let blocker_variations = enumerate_blocker_configs(possible_occupancy);
         for blockers in blocker_variations {
-            println!("");
+            println!();
             println!("The Blocker:");
             print_board(blockers);
             let attacks = bishop_attacks_from(notation_to_index("e4"), blockers);
             println!("The Attacks:");
             print_board(attacks);
-            println!("");
+            println!();
         }
     }
 }
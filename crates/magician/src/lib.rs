@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use std::sync::OnceLock;
+use std::sync::{Once, OnceLock};
 
 use attacks::build_attack_table_for_square;
 use bishop::bishop_attacks::bishop_attacks_from;
@@ -21,9 +21,14 @@ static BISHOP_ATTACK_TABLES: OnceLock<Vec<Vec<u64>>> = OnceLock::new();
 static BISHOP_MAGICS_SHIFTS: OnceLock<Vec<(u64, u8)>> = OnceLock::new();
 static BISHOP_OCCUPANCIES: OnceLock<Vec<u64>> = OnceLock::new();
 
+static INIT: Once = Once::new();
+
+// Safe to call repeatedly, the tables are only built once
 pub fn init_magician() {
-    init_rook_attacks();
-    init_bishop_attacks();
+    INIT.call_once(|| {
+        init_rook_attacks();
+        init_bishop_attacks();
+    });
 }
 
 fn init_bishop_attacks() {
@@ -40,9 +45,8 @@ fn init_bishop_attacks() {
     BISHOP_OCCUPANCIES.set(occupancies.clone()).ok();
 
     let mut tables = Vec::with_capacity(64);
-    for square in 0..64 {
+    for (square, &mask) in occupancies.iter().enumerate() {
         let (magic, _) = BISHOP_MAGICS_SHIFTS.get().unwrap()[square];
-        let mask = occupancies[square];
         let table = build_attack_table_for_square(square as u8, mask, magic, bishop_attacks_from);
         tables.push(table);
     }
@@ -77,9 +81,8 @@ fn init_rook_attacks() {
     ROOK_OCCUPANCIES.set(occupancies.clone()).ok();
 
     let mut tables = Vec::with_capacity(64);
-    for square in 0..64 {
+    for (square, &mask) in occupancies.iter().enumerate() {
         let (magic, _) = ROOK_MAGICS_SHIFTS.get().unwrap()[square];
-        let mask = occupancies[square];
         let table = build_attack_table_for_square(square as u8, mask, magic, rook_attacks_from);
         tables.push(table);
     }
@@ -45,9 +45,9 @@ mod test_rook {
         print_board(possible_occupancy);
         let blocker_variations = enumerate_blocker_configs(possible_occupancy);
         for variation in &blocker_variations {
-            println!("");
+            println!();
             print_board(*variation);
-            println!("");
+            println!();
         }
         assert_eq!(
             blocker_variations.len(),
@@ -57,13 +57,13 @@ mod test_rook {
         let possible_occupancy = rook_occupancy_mask(notation_to_index("e4"));
         let blocker_variations = enumerate_blocker_configs(possible_occupancy);
         for blockers in blocker_variations {
-            println!("");
+            println!();
             println!("The Blocker:");
             print_board(blockers);
             let attacks = rook_attacks_from(notation_to_index("e4"), blockers);
             println!("The Attacks:");
             print_board(attacks);
-            println!("");
+            println!();
         }
     }
 }
@@ -57,9 +57,9 @@ pub fn enumerate_blocker_configs(mask: u64) -> Vec<u64> {
 
     for i in 0..num_configs {
         let mut blocker = 0u64;
-        for j in 0..num_bits {
+        for (j, &bit) in relevant_bits.iter().enumerate() {
             if (i >> j) & 1 == 1 {
-                blocker |= 1u64 << relevant_bits[j];
+                blocker |= 1u64 << bit;
             }
         }
         configs.push(blocker);
@@ -0,0 +1,206 @@
+use arena::{
+    attacks::{king_attacks, knight_attacks, pawn_attacks},
+    board::Board,
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::{Color, PieceType},
+};
+use magician::{get_bishop_attacks, get_queen_attacks, get_rook_attacks};
+
+use crate::{
+    movegen::{GenType, is_tactical},
+    movelist::MoveList,
+};
+
+// The king is an ordinary piece, so pawns may promote to it too
+const PROMOTIONS: [PieceType; 5] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::King,
+];
+
+const RANK_1: u64 = 0x0000_0000_0000_00FF;
+const RANK_8: u64 = 0xFF00_0000_0000_0000;
+
+// Antichess moves: no check, no castling and no pins, but a capture must be
+// made whenever one is possible. Stages split the same way as in chess.
+pub(crate) fn generate_into(game: &Game, gen_type: GenType, list: &mut MoveList) {
+    if matches!(gen_type, GenType::Evasions | GenType::QuietChecks) {
+        return;
+    }
+    let mut captures = MoveList::new();
+    let mut quiets = MoveList::new();
+    generate_all(game, &mut captures, &mut quiets);
+    let moves = if captures.is_empty() {
+        quiets
+    } else {
+        captures
+    };
+    for &mv in &moves {
+        if gen_type.includes(is_tactical(mv)) {
+            list.push(mv);
+        }
+    }
+}
+
+// Whether the side to move has a capture, and so must make one. Stops at the
+// first piece found that can take something.
+pub(crate) fn has_capture(game: &Game) -> bool {
+    let us = game.side();
+    let occupancy = game.all_pieces().bits();
+    let theirs = game.color_pieces(us.opposite());
+    let ep = game.en_passant.map_or(0, |sq| 1 << sq);
+
+    for from in Board::new(game.color_pieces(us)).squares() {
+        let attacks = match game.get_piece_at(from).map(|p| p.piece_type()) {
+            Some(PieceType::Pawn) => pawn_attacks(us, from) & (theirs | ep),
+            Some(PieceType::Knight) => knight_attacks(from) & theirs,
+            Some(PieceType::Bishop) => get_bishop_attacks(from, occupancy) & theirs,
+            Some(PieceType::Rook) => get_rook_attacks(from, occupancy) & theirs,
+            Some(PieceType::Queen) => get_queen_attacks(from, occupancy) & theirs,
+            _ => king_attacks(from) & theirs,
+        };
+        if attacks != 0 {
+            return true;
+        }
+    }
+    false
+}
+
+fn generate_all(game: &Game, captures: &mut MoveList, quiets: &mut MoveList) {
+    let us = game.side();
+    let occupancy = game.all_pieces().bits();
+    let theirs = game.color_pieces(us.opposite());
+
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ] {
+        for from in Board::new(game.pieces(piece_type, us)).squares() {
+            let attacks = match piece_type {
+                PieceType::Knight => knight_attacks(from),
+                PieceType::Bishop => get_bishop_attacks(from, occupancy),
+                PieceType::Rook => get_rook_attacks(from, occupancy),
+                PieceType::Queen => get_queen_attacks(from, occupancy),
+                _ => king_attacks(from),
+            };
+            for to in Board::new(attacks & theirs).squares() {
+                captures.push(Move::capture(from, to));
+            }
+            for to in Board::new(attacks & !occupancy).squares() {
+                quiets.push(Move::quiet(from, to));
+            }
+        }
+    }
+
+    let (up, start_rank, promotion_rank): (i8, u64, u64) = match us {
+        Color::White => (8, 0x0000_0000_0000_FF00, RANK_8),
+        Color::Black => (-8, 0x00FF_0000_0000_0000, RANK_1),
+    };
+    let push_pawn_move = |from: u8, to: u8, capture: bool, list: &mut MoveList| {
+        if promotion_rank & (1 << to) != 0 {
+            for piece_type in PROMOTIONS {
+                list.push(Move::promotion(from, to, piece_type, capture));
+            }
+        } else if capture {
+            list.push(Move::capture(from, to));
+        } else {
+            list.push(Move::quiet(from, to));
+        }
+    };
+    for from in Board::new(game.pieces(PieceType::Pawn, us)).squares() {
+        let push = (from as i8 + up) as u8;
+        if occupancy & (1 << push) == 0 {
+            push_pawn_move(from, push, false, quiets);
+            let double = (push as i8 + up) as u8;
+            if start_rank & (1 << from) != 0 && occupancy & (1 << double) == 0 {
+                quiets.push(Move::new(from, double, MoveFlag::DoublePush));
+            }
+        }
+        for to in Board::new(pawn_attacks(us, from) & theirs).squares() {
+            push_pawn_move(from, to, true, captures);
+        }
+        if let Some(ep) = game.en_passant
+            && pawn_attacks(us, from) & (1 << ep) != 0
+        {
+            captures.push(Move::new(from, ep, MoveFlag::EnPassant));
+        }
+    }
+}
+
+#[cfg(test)]
+mod antichess_test {
+    use arena::{board::square, variant::Variant};
+
+    use super::*;
+    use crate::movegen::{generate, generate_legal};
+
+    fn antichess(fen: &str) -> Game {
+        Game::from_variant_fen(fen, Variant::Antichess).unwrap()
+    }
+
+    #[test]
+    fn test_captures_are_compulsory() {
+        // 1. e3 b5: white must take on b5
+        let game = antichess("rnbqkbnr/p1pppppp/8/1p6/8/4P3/PPPP1PPP/RNBQKBNR w - b6 0 2");
+        let moves = generate_legal(&game);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(
+            moves.first(),
+            Some(&Move::capture(square("f1"), square("b5")))
+        );
+
+        // Standard rules would let the king step away instead
+        let game = antichess("8/8/8/8/8/8/3q4/4K3 w - - 0 1");
+        assert_eq!(
+            &generate_legal(&game)[..],
+            [Move::capture(square("e1"), square("d2"))]
+        );
+    }
+
+    #[test]
+    fn test_king_is_ordinary() {
+        // The king may walk into attack, be left attacked and be taken
+        let game = antichess("8/8/8/8/8/8/r7/4K3 w - - 0 1");
+        assert_eq!(generate_legal(&game).len(), 5);
+        let game = antichess("8/8/8/8/8/8/8/r3K3 b - - 0 1");
+        assert!(generate_legal(&game).contains(&Move::capture(square("a1"), square("e1"))));
+        // No castling even with the pieces in place
+        let game = antichess("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        assert!(!generate_legal(&game).iter().any(|mv| mv.is_castle()));
+    }
+
+    #[test]
+    fn test_promotions() {
+        let game = antichess("8/P7/8/8/8/8/8/k7 w - - 0 1");
+        let moves = generate_legal(&game);
+        assert_eq!(moves.len(), 5);
+        assert!(moves.contains(&Move::promotion(
+            square("a7"),
+            square("a8"),
+            PieceType::King,
+            false
+        )));
+    }
+
+    #[test]
+    fn test_stages_partition_moves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "rnbqkbnr/p1pppppp/8/1p6/8/4P3/PPPP1PPP/RNBQKBNR w - b6 0 2",
+            "8/P7/8/8/8/8/8/k7 w - - 0 1",
+        ] {
+            let game = antichess(fen);
+            let all = generate_legal(&game).len();
+            let captures = generate(&game, GenType::Captures).len();
+            let quiets = generate(&game, GenType::Quiets).len();
+            assert_eq!(captures + quiets, all, "{fen}");
+            assert!(generate(&game, GenType::Evasions).is_empty());
+        }
+    }
+}
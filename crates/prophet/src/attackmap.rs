@@ -0,0 +1,172 @@
+use arena::{
+    attacks::{king_attacks, knight_attacks, line, pawn_attacks},
+    board::Board,
+    game::Game,
+    piece::{Color, Piece, PieceType},
+};
+use magician::{get_bishop_attacks, get_queen_attacks, get_rook_attacks};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceMobility {
+    pub square: u8,
+    pub piece: Piece,
+    pub attacks: Board,
+    // Attacked squares not holding a piece of the same side, kept to the pin
+    // line for a pinned piece
+    pub mobility: u32,
+}
+
+// Who attacks what, for both sides. Pawns show up in the maps but not in the
+// mobility list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackMap {
+    // Indexed by color
+    pub attacked: [Board; 2],
+    pub attacked_twice: [Board; 2],
+    // Indexed by color, then piece type
+    pub by_piece: [[Board; 6]; 2],
+    pub pieces: Vec<PieceMobility>,
+}
+
+impl AttackMap {
+    pub fn attacks_by(&self, color: Color, piece_type: PieceType) -> Board {
+        self.by_piece[color as usize][piece_type as usize]
+    }
+
+    pub fn mobility(&self, color: Color) -> u32 {
+        self.pieces
+            .iter()
+            .filter(|p| p.piece.color() == color)
+            .map(|p| p.mobility)
+            .sum()
+    }
+
+    pub fn mobility_of(&self, color: Color, piece_type: PieceType) -> u32 {
+        self.pieces
+            .iter()
+            .filter(|p| p.piece == Piece::new(piece_type, color))
+            .map(|p| p.mobility)
+            .sum()
+    }
+}
+
+// Builds the maps in one walk over the pieces
+pub fn attack_map(game: &Game) -> AttackMap {
+    let occupancy = game.all_pieces().bits();
+    let mut attacked = [0u64; 2];
+    let mut twice = [0u64; 2];
+    let mut by_piece = [[0u64; 6]; 2];
+    let mut pieces = Vec::with_capacity(32);
+
+    for sq in Board::new(occupancy).squares() {
+        let piece = game.get_piece_at(sq).unwrap();
+        let color = piece.color();
+        let c = color as usize;
+        let attacks = match piece.piece_type() {
+            PieceType::Pawn => pawn_attacks(color, sq),
+            PieceType::Knight => knight_attacks(sq),
+            PieceType::Bishop => get_bishop_attacks(sq, occupancy),
+            PieceType::Rook => get_rook_attacks(sq, occupancy),
+            PieceType::Queen => get_queen_attacks(sq, occupancy),
+            PieceType::King => king_attacks(sq),
+        };
+        twice[c] |= attacked[c] & attacks;
+        attacked[c] |= attacks;
+        by_piece[c][piece.piece_type() as usize] |= attacks;
+
+        if piece.piece_type() == PieceType::Pawn {
+            continue;
+        }
+        let mut reach = attacks & !game.color_pieces(color);
+        if game.pinned(color).has_bit(sq)
+            && let Some(king) = game.king_square(color)
+        {
+            reach &= line(king, sq);
+        }
+        pieces.push(PieceMobility {
+            square: sq,
+            piece,
+            attacks: Board::new(attacks),
+            mobility: reach.count_ones(),
+        });
+    }
+
+    AttackMap {
+        attacked: attacked.map(Board::new),
+        attacked_twice: twice.map(Board::new),
+        by_piece: by_piece.map(|boards| boards.map(Board::new)),
+        pieces,
+    }
+}
+
+#[cfg(test)]
+mod attackmap_test {
+    use arena::board::square;
+
+    use super::*;
+
+    #[test]
+    fn test_matches_attackers_to() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            let map = attack_map(&game);
+            let occupancy = game.all_pieces().bits();
+            for square in 0..64 {
+                let attackers = game.attackers_to(square, occupancy).bits();
+                for color in [Color::White, Color::Black] {
+                    let c = color as usize;
+                    let ours = attackers & game.color_pieces(color);
+                    assert_eq!(map.attacked[c].has_bit(square), ours != 0);
+                    assert_eq!(map.attacked_twice[c].has_bit(square), ours.count_ones() > 1);
+                    for piece_type in [
+                        PieceType::Pawn,
+                        PieceType::Knight,
+                        PieceType::Bishop,
+                        PieceType::Rook,
+                        PieceType::Queen,
+                        PieceType::King,
+                    ] {
+                        let by_type = ours & game.pieces(piece_type, color);
+                        assert_eq!(
+                            map.attacks_by(color, piece_type).has_bit(square),
+                            by_type != 0
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_start_position_mobility() {
+        let map = attack_map(&Game::new());
+        assert_eq!(map.mobility_of(Color::White, PieceType::Knight), 4);
+        assert_eq!(map.mobility_of(Color::White, PieceType::Bishop), 0);
+        assert_eq!(map.mobility(Color::White), 4);
+        assert_eq!(map.mobility(Color::Black), 4);
+        assert_eq!(map.pieces.len(), 16);
+        // f3 is covered by both pawns beside it and the knight, b1 only by the rook
+        let white = Color::White as usize;
+        assert!(map.attacked_twice[white].has_bit(square("f3")));
+        assert!(map.attacked[white].has_bit(square("b1")));
+        assert!(!map.attacked_twice[white].has_bit(square("b1")));
+    }
+
+    #[test]
+    fn test_pinned_mobility() {
+        // The bishop on d2 is pinned by the a5 queen and can only slide along the pin
+        let game = Game::from_fen("4k3/8/8/q7/8/8/3B4/4K3 w - - 0 1").unwrap();
+        let map = attack_map(&game);
+        let bishop = map
+            .pieces
+            .iter()
+            .find(|p| p.square == square("d2"))
+            .unwrap();
+        assert_eq!(bishop.attacks.count(), 9);
+        assert_eq!(bishop.mobility, 3);
+    }
+}
@@ -0,0 +1,261 @@
+use arena::{
+    attacks::{between, king_attacks, knight_attacks, line, pawn_attacks},
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::{Color, PieceType},
+    variant::Variant,
+};
+use magician::{get_bishop_attacks, get_queen_attacks, get_rook_attacks};
+
+use crate::{
+    antichess::has_capture,
+    movegen::{castling_is_legal, en_passant_is_legal},
+};
+
+const RANK_1: u64 = 0x0000_0000_0000_00FF;
+const RANK_8: u64 = 0xFF00_0000_0000_0000;
+
+// Whether the move could have come from a pseudo-legal generator here: our
+// piece moving by its rules with a flag that fits the board. It may still leave
+// our king in check, apart from castling which is checked in full.
+pub fn is_pseudo_legal(game: &Game, mv: Move) -> bool {
+    if !piece_can_move(game, mv) {
+        return false;
+    }
+    // Captures are compulsory in Antichess
+    game.variant != Variant::Antichess || mv.is_capture() || !has_capture(game)
+}
+
+fn piece_can_move(game: &Game, mv: Move) -> bool {
+    if mv.is_null() {
+        return false;
+    }
+    let antichess = game.variant == Variant::Antichess;
+    if mv.promotion_piece() == Some(PieceType::King) && !antichess {
+        return false;
+    }
+    let us = game.side();
+    let (from, to) = (mv.from(), mv.to());
+    let Some(piece) = game.get_piece_at(from) else {
+        return false;
+    };
+    if piece.color() != us {
+        return false;
+    }
+    let occupancy = game.all_pieces().bits();
+
+    if mv.is_castle() {
+        if antichess {
+            return false;
+        }
+        let kingside = mv.flag() == MoveFlag::KingCastle;
+        return piece.piece_type() == PieceType::King
+            && to == Game::castling_destinations(us, kingside).0
+            && !game.is_in_check()
+            && castling_is_legal(game, us, from, kingside, occupancy);
+    }
+
+    // Capture flags need an enemy piece on the target, other than a royal king,
+    // everything else an empty square
+    let them = us.opposite();
+    let mut victims = game.color_pieces(them);
+    if !antichess {
+        victims &= !game.pieces(PieceType::King, them);
+    }
+    let target_fits = if mv.is_capture() && !mv.is_en_passant() {
+        victims & (1 << to) != 0
+    } else {
+        occupancy & (1 << to) == 0
+    };
+    if !target_fits {
+        return false;
+    }
+
+    let piece_type = piece.piece_type();
+    if piece_type != PieceType::Pawn {
+        if !matches!(mv.flag(), MoveFlag::Quiet | MoveFlag::Capture) {
+            return false;
+        }
+        let attacks = match piece_type {
+            PieceType::Knight => knight_attacks(from),
+            PieceType::Bishop => get_bishop_attacks(from, occupancy),
+            PieceType::Rook => get_rook_attacks(from, occupancy),
+            PieceType::Queen => get_queen_attacks(from, occupancy),
+            _ => king_attacks(from),
+        };
+        return attacks & (1 << to) != 0;
+    }
+
+    let (up, start_rank, promotion_rank): (i8, u64, u64) = match us {
+        Color::White => (8, 0x0000_0000_0000_FF00, RANK_8),
+        Color::Black => (-8, 0x00FF_0000_0000_0000, RANK_1),
+    };
+    if mv.is_promotion() != (promotion_rank & (1 << to) != 0) {
+        return false;
+    }
+    let push = (from as i8 + up) as u8;
+    match mv.flag() {
+        MoveFlag::EnPassant => {
+            let captured = (to as i8 - up) as u8;
+            game.en_passant == Some(to)
+                && pawn_attacks(us, from) & (1 << to) != 0
+                && game.pieces(PieceType::Pawn, them) & (1 << captured) != 0
+        }
+        MoveFlag::DoublePush => {
+            start_rank & (1 << from) != 0
+                && to as i8 == push as i8 + up
+                && occupancy & (1 << push) == 0
+        }
+        _ if mv.is_capture() => pawn_attacks(us, from) & (1 << to) != 0,
+        _ => to == push,
+    }
+}
+
+// Whether the move is in the legal move list, without generating it
+pub fn is_legal(game: &Game, mv: Move) -> bool {
+    if !is_pseudo_legal(game, mv) || game.variant_goal_reached() {
+        return false;
+    }
+    if mv.is_castle() || game.variant == Variant::Antichess {
+        return true;
+    }
+    let us = game.side();
+    let Some(king) = game.king_square(us) else {
+        return false;
+    };
+    let (from, to) = (mv.from(), mv.to());
+    let occupancy = game.all_pieces().bits();
+
+    if from == king {
+        // Off the board, so it can't shield the destination from a slider
+        let without_king = occupancy & !(1 << king);
+        let theirs = game.color_pieces(us.opposite());
+        return game.attackers_to(to, without_king).bits() & theirs == 0;
+    }
+    if mv.is_en_passant() {
+        return en_passant_is_legal(game, us, king, from, to, occupancy);
+    }
+
+    let checkers = game.checkers().bits();
+    if checkers.count_ones() > 1 {
+        return false;
+    }
+    if checkers != 0 {
+        let check_mask = checkers | between(king, checkers.trailing_zeros() as u8);
+        if check_mask & (1 << to) == 0 {
+            return false;
+        }
+    }
+    game.pinned(us).bits() & (1 << from) == 0 || line(king, from) & (1 << to) != 0
+}
+
+#[cfg(test)]
+mod legality_test {
+    use arena::{board::square, fen::START_FEN};
+
+    use super::*;
+    use crate::movegen::generate_legal;
+
+    // Every encodable move
+    fn all_moves() -> impl Iterator<Item = Move> {
+        (0..=u16::MAX)
+            .map(Move::from_raw)
+            .filter(|mv| !mv.is_null())
+    }
+
+    #[test]
+    fn test_matches_generator() {
+        for fen in [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+            "4r2k/8/8/8/7b/8/3Q4/4K3 w - - 0 1",
+        ] {
+            let mut game = Game::from_fen(fen).unwrap();
+            let legal = generate_legal(&game);
+            let mut pseudo_only = Vec::new();
+            for mv in all_moves() {
+                assert_eq!(is_legal(&game, mv), legal.contains(&mv), "{mv:?} in {fen}");
+                if is_pseudo_legal(&game, mv) && !legal.contains(&mv) {
+                    pseudo_only.push(mv);
+                }
+            }
+            assert!(legal.iter().all(|&mv| is_pseudo_legal(&game, mv)));
+
+            // What pseudo-legality lets through must only fail on king safety
+            let us = game.side();
+            for mv in pseudo_only {
+                assert!(!mv.is_castle());
+                game.make_move(mv);
+                let king = game.king_square(us).unwrap();
+                assert!(
+                    game.is_square_attacked(king, us.opposite()),
+                    "{mv:?} in {fen}"
+                );
+                game.unmake_move();
+            }
+        }
+    }
+
+    #[test]
+    fn test_antichess_matches_generator() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "rnbqkbnr/p1pppppp/8/1p6/8/4P3/PPPP1PPP/RNBQKBNR w - b6 0 2",
+            "8/8/8/3pP3/8/8/8/8 w - d6 0 1",
+            "8/1P6/8/8/8/8/8/k6K w - - 0 1",
+            "2k5/1P6/8/8/8/8/8/R6K w - - 0 1",
+            "8/8/8/8/8/8/1b6/B7 w - - 0 1",
+        ] {
+            let game = Game::from_variant_fen(fen, Variant::Antichess).unwrap();
+            let legal = generate_legal(&game);
+            for mv in all_moves() {
+                assert_eq!(is_legal(&game, mv), legal.contains(&mv), "{mv:?} in {fen}");
+            }
+        }
+        // Kings promote only where they are ordinary pieces
+        let game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mv = Move::promotion(square("b7"), square("b8"), PieceType::King, false);
+        assert!(!is_pseudo_legal(&game, mv));
+        assert!(is_legal(&game.clone().with_variant(Variant::Antichess), mv));
+    }
+
+    #[test]
+    fn test_rejects_mismatched_moves() {
+        let game = Game::new();
+        // Right squares, wrong flag
+        assert!(!is_pseudo_legal(
+            &game,
+            Move::quiet(square("e2"), square("e4"))
+        ));
+        assert!(is_pseudo_legal(
+            &game,
+            Move::new(square("e2"), square("e4"), MoveFlag::DoublePush)
+        ));
+        assert!(!is_pseudo_legal(
+            &game,
+            Move::capture(square("g1"), square("f3"))
+        ));
+        // Not our piece, or not how it moves
+        assert!(!is_pseudo_legal(
+            &game,
+            Move::quiet(square("e7"), square("e6"))
+        ));
+        assert!(!is_pseudo_legal(
+            &game,
+            Move::quiet(square("f1"), square("c4"))
+        ));
+        assert!(!is_pseudo_legal(&game, Move::NULL));
+
+        // An en passant flag only works on the recorded square
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!is_legal(
+            &game,
+            Move::new(square("e5"), square("d6"), MoveFlag::EnPassant)
+        ));
+    }
+}
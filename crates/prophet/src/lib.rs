@@ -1,14 +1,23 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+mod antichess;
+pub mod attackmap;
+pub mod legality;
+pub mod movegen;
+pub mod movelist;
+pub mod movepick;
+pub mod perft;
+pub mod predict;
+pub mod queries;
+pub mod see;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use attackmap::{AttackMap, PieceMobility, attack_map};
+pub use legality::{is_legal, is_pseudo_legal};
+pub use movegen::{
+    GenType, generate_captures, generate_evasions, generate_legal, generate_quiet_checks,
+    generate_quiets, gives_check,
+};
+pub use movelist::MoveList;
+pub use movepick::{History, MovePicker};
+pub use perft::{PerftReport, PerftTable, divide, perft, perft_hashed, perft_parallel};
+pub use predict::{OpeningBook, Prediction, PredictionConfig, predict_replies};
+pub use queries::{destinations, find_move, moves_from, moves_to, origins, promotion_choices};
+pub use see::{see, see_ge, square_threat, threatened_squares};
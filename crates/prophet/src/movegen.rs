@@ -0,0 +1,611 @@
+use arena::{
+    attacks::{between, king_attacks, knight_attacks, line, pawn_attacks},
+    board::Board,
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::{Color, PieceType},
+    variant::Variant,
+};
+use magician::{get_bishop_attacks, get_queen_attacks, get_rook_attacks};
+
+use crate::{antichess, movelist::MoveList};
+
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+const RANK_1: u64 = 0x0000_0000_0000_00FF;
+const RANK_8: u64 = 0xFF00_0000_0000_0000;
+
+// Which slice of the legal moves to produce. Captures also take queen
+// promotions, so quiescence sees them; underpromotions without a capture count
+// as quiet. Captures and quiets together are exactly the legal moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenType {
+    All,
+    Captures,
+    Quiets,
+    // Every legal move while in check, nothing otherwise
+    Evasions,
+    // The quiet moves which give check
+    QuietChecks,
+}
+
+impl GenType {
+    pub(crate) fn includes(self, tactical: bool) -> bool {
+        match self {
+            GenType::All | GenType::Evasions => true,
+            GenType::Captures => tactical,
+            GenType::Quiets | GenType::QuietChecks => !tactical,
+        }
+    }
+}
+
+// Whether a move lands in the captures stage rather than the quiets
+pub fn is_tactical(mv: Move) -> bool {
+    mv.is_capture() || mv.promotion_piece() == Some(PieceType::Queen)
+}
+
+pub fn generate_legal(game: &Game) -> MoveList {
+    generate(game, GenType::All)
+}
+
+pub fn generate_legal_into(game: &Game, list: &mut MoveList) {
+    generate_into(game, GenType::All, list);
+}
+
+pub fn generate_captures(game: &Game) -> MoveList {
+    generate(game, GenType::Captures)
+}
+
+pub fn generate_quiets(game: &Game) -> MoveList {
+    generate(game, GenType::Quiets)
+}
+
+pub fn generate_evasions(game: &Game) -> MoveList {
+    generate(game, GenType::Evasions)
+}
+
+pub fn generate_quiet_checks(game: &Game) -> MoveList {
+    generate(game, GenType::QuietChecks)
+}
+
+pub fn generate(game: &Game, gen_type: GenType) -> MoveList {
+    let mut list = MoveList::new();
+    generate_into(game, gen_type, &mut list);
+    list
+}
+
+// Appends the moves of one stage to `list`, by the rules of the game's variant
+pub fn generate_into(game: &Game, gen_type: GenType, list: &mut MoveList) {
+    if game.variant == Variant::Antichess {
+        return antichess::generate_into(game, gen_type, list);
+    }
+    // A king on the hill or a third check has already ended the game
+    if game.variant_goal_reached() {
+        return;
+    }
+    match gen_type {
+        GenType::All | GenType::Captures | GenType::Quiets => generate_moves(game, gen_type, list),
+        GenType::Evasions => {
+            if game.is_in_check() {
+                generate_moves(game, GenType::All, list);
+            }
+        }
+        GenType::QuietChecks => {
+            let mut quiets = MoveList::new();
+            generate_moves(game, GenType::Quiets, &mut quiets);
+            for &mv in &quiets {
+                if gives_check(game, mv) {
+                    list.push(mv);
+                }
+            }
+        }
+    }
+}
+
+// Legal moves only, using the check and pin masks cached on the game instead of
+// trying each move with make/unmake
+fn generate_moves(game: &Game, gen_type: GenType, list: &mut MoveList) {
+    let us = game.side();
+    let Some(king) = game.king_square(us) else {
+        return;
+    };
+    let them = us.opposite();
+    let occupancy = game.all_pieces().bits();
+    let ours = game.color_pieces(us);
+    let theirs = game.color_pieces(them);
+    let targets = match gen_type {
+        GenType::Captures => theirs,
+        GenType::Quiets => !occupancy,
+        _ => !ours,
+    };
+
+    generate_king_moves(game, king, targets, theirs, occupancy, list);
+
+    let checkers = game.checkers().bits();
+    if checkers.count_ones() > 1 {
+        // Double check, only the king can move
+        return;
+    }
+    let check_mask = if checkers != 0 {
+        checkers | between(king, checkers.trailing_zeros() as u8)
+    } else {
+        !0
+    };
+    let pinned = game.pinned(us).bits();
+
+    // Pinned pieces may only slide along the line through their king
+    let allowed = |from: u8| {
+        if pinned & (1 << from) != 0 {
+            check_mask & line(king, from)
+        } else {
+            check_mask
+        }
+    };
+
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ] {
+        for from in Board::new(game.pieces(piece_type, us)).squares() {
+            let attacks = match piece_type {
+                PieceType::Knight => knight_attacks(from),
+                PieceType::Bishop => get_bishop_attacks(from, occupancy),
+                PieceType::Rook => get_rook_attacks(from, occupancy),
+                _ => get_queen_attacks(from, occupancy),
+            };
+            push_targets(from, attacks & targets & allowed(from), theirs, list);
+        }
+    }
+
+    generate_pawn_moves(game, gen_type, king, occupancy, theirs, &allowed, list);
+
+    if checkers == 0 && gen_type != GenType::Captures {
+        generate_castling(game, us, king, occupancy, list);
+    }
+}
+
+// Tests the position after the move for attacks on the enemy king, covering
+// direct checks, discovered checks, promotions, en passant and castling rooks
+pub fn gives_check(game: &Game, mv: Move) -> bool {
+    if !game.variant.has_royal_king() {
+        return false;
+    }
+    let us = game.side();
+    let Some(king) = game.king_square(us.opposite()) else {
+        return false;
+    };
+    let Some(piece) = game.get_piece_at(mv.from()) else {
+        return false;
+    };
+    let (from, to) = (mv.from(), mv.to());
+    let mut occupancy = (game.all_pieces().bits() & !(1 << from)) | (1 << to);
+    let mut diagonal =
+        (game.pieces(PieceType::Bishop, us) | game.pieces(PieceType::Queen, us)) & !(1 << from);
+    let mut orthogonal =
+        (game.pieces(PieceType::Rook, us) | game.pieces(PieceType::Queen, us)) & !(1 << from);
+
+    if mv.is_en_passant() {
+        let captured = match us {
+            Color::White => to - 8,
+            Color::Black => to + 8,
+        };
+        occupancy &= !(1 << captured);
+    } else if mv.is_castle() {
+        let kingside = mv.flag() == MoveFlag::KingCastle;
+        let rook = game.castling_rook_square(us, kingside);
+        let (king_to, rook_to) = Game::castling_destinations(us, kingside);
+        occupancy = (occupancy & !(1 << rook)) | (1 << king_to) | (1 << rook_to);
+        orthogonal = (orthogonal & !(1 << rook)) | (1 << rook_to);
+    }
+
+    match mv.promotion_piece().unwrap_or(piece.piece_type()) {
+        PieceType::Pawn => {
+            if pawn_attacks(us, to) & (1 << king) != 0 {
+                return true;
+            }
+        }
+        PieceType::Knight => {
+            if knight_attacks(to) & (1 << king) != 0 {
+                return true;
+            }
+        }
+        PieceType::Bishop => diagonal |= 1 << to,
+        PieceType::Rook => orthogonal |= 1 << to,
+        PieceType::Queen => {
+            diagonal |= 1 << to;
+            orthogonal |= 1 << to;
+        }
+        PieceType::King => {}
+    }
+
+    get_bishop_attacks(king, occupancy) & diagonal != 0
+        || get_rook_attacks(king, occupancy) & orthogonal != 0
+}
+
+fn push_targets(from: u8, targets: u64, theirs: u64, list: &mut MoveList) {
+    for to in Board::new(targets).squares() {
+        if theirs & (1 << to) != 0 {
+            list.push(Move::capture(from, to));
+        } else {
+            list.push(Move::quiet(from, to));
+        }
+    }
+}
+
+fn generate_king_moves(
+    game: &Game,
+    king: u8,
+    targets: u64,
+    theirs: u64,
+    occupancy: u64,
+    list: &mut MoveList,
+) {
+    // The king must not hide behind itself from a slider, so take it off the board
+    let without_king = occupancy & !(1 << king);
+    for to in Board::new(king_attacks(king) & targets).squares() {
+        if game.attackers_to(to, without_king).bits() & theirs == 0 {
+            if theirs & (1 << to) != 0 {
+                list.push(Move::capture(king, to));
+            } else {
+                list.push(Move::quiet(king, to));
+            }
+        }
+    }
+}
+
+fn generate_pawn_moves(
+    game: &Game,
+    gen_type: GenType,
+    king: u8,
+    occupancy: u64,
+    theirs: u64,
+    allowed: &impl Fn(u8) -> u64,
+    list: &mut MoveList,
+) {
+    let us = game.side();
+    let (up, start_rank, promotion_rank): (i8, u64, u64) = match us {
+        Color::White => (8, 0x0000_0000_0000_FF00, RANK_8),
+        Color::Black => (-8, 0x00FF_0000_0000_0000, RANK_1),
+    };
+    let quiets = gen_type.includes(false);
+    let captures = gen_type.includes(true);
+
+    for from in Board::new(game.pieces(PieceType::Pawn, us)).squares() {
+        let mask = allowed(from);
+
+        let push = (from as i8 + up) as u8;
+        if occupancy & (1 << push) == 0 {
+            if mask & (1 << push) != 0 {
+                push_pawn_move(from, push, false, promotion_rank, gen_type, list);
+            }
+            let double = (push as i8 + up) as u8;
+            if quiets
+                && start_rank & (1 << from) != 0
+                && occupancy & (1 << double) == 0
+                && mask & (1 << double) != 0
+            {
+                list.push(Move::new(from, double, MoveFlag::DoublePush));
+            }
+        }
+
+        if !captures {
+            continue;
+        }
+        for to in Board::new(pawn_attacks(us, from) & theirs & mask).squares() {
+            push_pawn_move(from, to, true, promotion_rank, gen_type, list);
+        }
+
+        if let Some(ep) = game.en_passant
+            && pawn_attacks(us, from) & (1 << ep) != 0
+            && en_passant_is_legal(game, us, king, from, ep, occupancy)
+        {
+            list.push(Move::new(from, ep, MoveFlag::EnPassant));
+        }
+    }
+}
+
+fn push_pawn_move(
+    from: u8,
+    to: u8,
+    capture: bool,
+    promotion_rank: u64,
+    gen_type: GenType,
+    list: &mut MoveList,
+) {
+    if promotion_rank & (1 << to) != 0 {
+        for piece_type in PROMOTIONS {
+            if gen_type.includes(capture || piece_type == PieceType::Queen) {
+                list.push(Move::promotion(from, to, piece_type, capture));
+            }
+        }
+    } else if gen_type.includes(capture) {
+        if capture {
+            list.push(Move::capture(from, to));
+        } else {
+            list.push(Move::quiet(from, to));
+        }
+    }
+}
+
+// En passant removes two pawns from one rank at once, which the pin mask can't
+// see (king and rook on the same rank as both pawns), so play it out on the
+// occupancy instead
+pub(crate) fn en_passant_is_legal(
+    game: &Game,
+    us: Color,
+    king: u8,
+    from: u8,
+    ep: u8,
+    occupancy: u64,
+) -> bool {
+    let captured = match us {
+        Color::White => ep - 8,
+        Color::Black => ep + 8,
+    };
+    if game.get_piece_at(captured).map(|p| p.piece_type()) != Some(PieceType::Pawn) {
+        return false;
+    }
+    let after = (occupancy & !(1 << from) & !(1 << captured)) | (1 << ep);
+    let attackers =
+        game.attackers_to(king, after).bits() & game.color_pieces(us.opposite()) & !(1 << captured);
+    attackers == 0
+}
+
+fn generate_castling(game: &Game, us: Color, king: u8, occupancy: u64, list: &mut MoveList) {
+    for kingside in [true, false] {
+        if castling_is_legal(game, us, king, kingside, occupancy) {
+            let (king_to, _) = Game::castling_destinations(us, kingside);
+            let flag = if kingside {
+                MoveFlag::KingCastle
+            } else {
+                MoveFlag::QueenCastle
+            };
+            list.push(Move::new(king, king_to, flag));
+        }
+    }
+}
+
+// Expects the side to move not to be in check
+pub(crate) fn castling_is_legal(
+    game: &Game,
+    us: Color,
+    king: u8,
+    kingside: bool,
+    occupancy: u64,
+) -> bool {
+    if !game.has_castling_right(us, kingside) {
+        return false;
+    }
+    let rook = game.castling_rook_square(us, kingside);
+    if game.pieces(PieceType::Rook, us) & (1 << rook) == 0 {
+        return false;
+    }
+    let (king_to, rook_to) = Game::castling_destinations(us, kingside);
+
+    // Everything either piece passes over or lands on must be empty,
+    // apart from the castling king and rook themselves
+    let king_path = between(king, king_to) | (1 << king_to);
+    let rook_path = between(rook, rook_to) | (1 << rook_to);
+    let others = occupancy & !(1 << king) & !(1 << rook);
+    if (king_path | rook_path) & others != 0 {
+        return false;
+    }
+
+    // The king may not pass through or land on an attacked square
+    let them = game.color_pieces(us.opposite());
+    Board::new(king_path)
+        .squares()
+        .all(|sq| game.attackers_to(sq, others).bits() & them == 0)
+}
+
+#[cfg(test)]
+mod movegen_test {
+    use arena::{board::square, fen::START_FEN};
+
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn moves_of(fen: &str) -> Vec<String> {
+        let game = Game::from_fen(fen).expect("valid FEN");
+        let mut moves: Vec<String> = generate_legal(&game)
+            .iter()
+            .map(|mv| game.move_to_uci(*mv))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn test_move_counts() {
+        let positions = [
+            (START_FEN, 20),
+            (KIWIPETE, 48),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 14),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                6,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                44,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                46,
+            ),
+        ];
+        for (fen, expected) in positions {
+            assert_eq!(moves_of(fen).len(), expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_en_passant_horizontal_pin() {
+        // Taking en passant would leave the rank between the rook and king empty
+        let moves = moves_of("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1");
+        assert!(!moves.contains(&"e5d6".to_string()));
+
+        // Without the rook the capture is fine
+        let moves = moves_of("8/8/8/K2pP3/8/8/8/7k w - d6 0 1");
+        assert!(moves.contains(&"e5d6".to_string()));
+    }
+
+    #[test]
+    fn test_en_passant_resolves_check() {
+        // The double-pushed pawn gives check and can be taken en passant
+        let moves = moves_of("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        assert!(moves.contains(&"e4d3".to_string()));
+        assert!(moves.iter().all(|mv| mv.starts_with("c5") || mv == "e4d3"));
+    }
+
+    #[test]
+    fn test_castling_through_attacked_squares() {
+        // Bishop on a6 covers f1, so only queenside castling is allowed
+        let moves = moves_of("r3k2r/8/b7/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+
+        // The rook crosses b1, so it must be empty but may be attacked
+        let moves = moves_of("1r2k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1");
+        assert!(moves.contains(&"e1c1".to_string()));
+        let moves = moves_of("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1");
+        assert!(!moves.contains(&"e1c1".to_string()));
+
+        // No castling out of check
+        let moves = moves_of("r3k2r/8/8/8/8/8/4q3/R3K2R w KQkq - 0 1");
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(!moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // King c1 with rooks b1 and h1, the b1 rook shields c1 from the a1 rook
+        let game = Game::from_fen("k7/8/8/8/8/8/8/rRK4R w KQ - 0 1").unwrap();
+        assert!(game.chess960);
+        let castles: Vec<String> = generate_legal(&game)
+            .iter()
+            .filter(|mv| mv.is_castle())
+            .map(|mv| game.move_to_uci(*mv))
+            .collect();
+        // Castling queenside moves that rook away and leaves the king in check
+        assert_eq!(castles, vec!["c1h1"]);
+    }
+
+    #[test]
+    fn test_promotions() {
+        let moves = moves_of("1n5k/P7/8/8/8/8/8/7K w - - 0 1");
+        for promotion in ["a7a8q", "a7a8r", "a7a8b", "a7a8n", "a7b8q", "a7b8n"] {
+            assert!(moves.contains(&promotion.to_string()), "{promotion}");
+        }
+        assert_eq!(moves.iter().filter(|mv| mv.len() == 5).count(), 8);
+    }
+
+    #[test]
+    fn test_pinned_and_double_check() {
+        // Knight on e2 is pinned by the e8 rook and can't move at all
+        let game = Game::from_fen("4r2k/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        let moves = generate_legal(&game);
+        assert!(moves.iter().all(|mv| mv.from() != square("e2")));
+
+        // Double check from rook and bishop leaves only king moves
+        let game = Game::from_fen("4r2k/8/8/8/7b/8/3Q4/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.checkers().count(), 2);
+        let moves = generate_legal(&game);
+        assert!(moves.iter().all(|mv| mv.from() == square("e1")));
+    }
+
+    fn sorted(moves: &[Move]) -> Vec<u16> {
+        let mut raw: Vec<u16> = moves.iter().map(|mv| mv.raw()).collect();
+        raw.sort();
+        raw
+    }
+
+    // Checks every stage against the full generator at each node of a small tree
+    fn check_stages(game: &mut Game, depth: u32) {
+        let legal = generate_legal(game);
+        let captures = generate_captures(game);
+        let quiets = generate_quiets(game);
+        assert!(captures.iter().all(|&mv| is_tactical(mv)));
+        assert!(quiets.iter().all(|&mv| !is_tactical(mv)));
+        let mut union: Vec<Move> = captures.to_vec();
+        union.extend_from_slice(&quiets);
+        assert_eq!(sorted(&union), sorted(&legal), "{}", game.to_fen());
+
+        let evasions = generate_evasions(game);
+        if game.is_in_check() {
+            assert_eq!(sorted(&evasions), sorted(&legal));
+        } else {
+            assert!(evasions.is_empty());
+        }
+
+        let mut expected_checks = Vec::new();
+        for &mv in &legal {
+            game.make_move(mv);
+            let check = game.is_in_check();
+            game.unmake_move();
+            assert_eq!(gives_check(game, mv), check, "{mv} in {}", game.to_fen());
+            if check && !is_tactical(mv) {
+                expected_checks.push(mv);
+            }
+        }
+        assert_eq!(
+            sorted(&generate_quiet_checks(game)),
+            sorted(&expected_checks)
+        );
+
+        if depth > 1 {
+            for &mv in &legal {
+                game.make_move(mv);
+                check_stages(game, depth - 1);
+                game.unmake_move();
+            }
+        }
+    }
+
+    #[test]
+    fn test_stages_partition_legal_moves() {
+        for fen in [
+            START_FEN,
+            KIWIPETE,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ] {
+            let mut game = Game::from_fen(fen).unwrap();
+            check_stages(&mut game, 2);
+        }
+    }
+
+    #[test]
+    fn test_quiet_checks() {
+        // Castling checks with the rook, the underpromotion to a knight checks
+        // and the queen promotion is left to the captures stage
+        let game = Game::from_fen("5k2/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let checks: Vec<String> = generate_quiet_checks(&game)
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect();
+        assert!(checks.contains(&"e1g1".to_string()));
+        assert!(checks.contains(&"h1h8".to_string()));
+        assert!(!checks.contains(&"b7b8q".to_string()));
+        assert!(
+            generate_captures(&game)
+                .iter()
+                .any(|mv| mv.to_string() == "b7b8q")
+        );
+
+        // En passant takes both pawns off the rank and uncovers the rook
+        let game = Game::from_fen("8/8/8/k2pP2R/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mv = Move::new(square("e5"), square("d6"), MoveFlag::EnPassant);
+        assert!(gives_check(&game, mv));
+    }
+}
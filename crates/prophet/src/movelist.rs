@@ -0,0 +1,98 @@
+use std::ops::{Deref, DerefMut};
+
+use arena::moves::Move;
+
+// Enough for any reachable position, the known maximum is 218
+pub const MAX_MOVES: usize = 256;
+
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        MoveList {
+            moves: [Move::NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn swap_remove(&mut self, idx: usize) -> Move {
+        let mv = self.moves[idx];
+        self.len -= 1;
+        self.moves[idx] = self.moves[self.len];
+        mv
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves[..self.len].iter()
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod movelist_test {
+    use super::*;
+
+    #[test]
+    fn test_push_and_remove() {
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+
+        let a = Move::quiet(12, 28);
+        let b = Move::quiet(6, 21);
+        let c = Move::capture(1, 18);
+        list.push(a);
+        list.push(b);
+        list.push(c);
+        assert_eq!(list.len(), 3);
+        assert!(list.contains(&b));
+
+        assert_eq!(list.swap_remove(0), a);
+        assert_eq!(&list[..], &[c, b]);
+
+        list.clear();
+        assert!(list.is_empty());
+    }
+}
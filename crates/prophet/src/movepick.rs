@@ -0,0 +1,486 @@
+use arena::{
+    game::Game,
+    moves::Move,
+    piece::{Color, PieceType},
+};
+
+use crate::{
+    legality::is_legal,
+    movegen::{GenType, generate_into, is_tactical},
+    movelist::{MAX_MOVES, MoveList},
+    see::see_ge,
+};
+
+// Rough piece values for ordering captures, indexed by piece type
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+pub const MAX_HISTORY: i32 = 16384;
+
+// Butterfly history for quiet moves, indexed by side, from and to square
+#[derive(Clone)]
+pub struct History {
+    table: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            table: Box::new([[[0; 64]; 64]; 2]),
+        }
+    }
+
+    pub fn get(&self, color: Color, mv: Move) -> i32 {
+        self.table[color as usize][mv.from() as usize][mv.to() as usize]
+    }
+
+    // Gravity update, so entries saturate towards +-MAX_HISTORY instead of
+    // growing without bound
+    pub fn update(&mut self, color: Color, mv: Move, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+        let entry = &mut self.table[color as usize][mv.from() as usize][mv.to() as usize];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    pub fn clear(&mut self) {
+        *self.table = [[[0; 64]; 64]; 2];
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    HashMove,
+    GoodCaptures,
+    Killers,
+    CounterMove,
+    Quiets,
+    BadCaptures,
+    EvasionHashMove,
+    Evasions,
+    Done,
+}
+
+struct ScoredList {
+    moves: MoveList,
+    scores: [i32; MAX_MOVES],
+}
+
+impl ScoredList {
+    fn new() -> Self {
+        ScoredList {
+            moves: MoveList::new(),
+            scores: [0; MAX_MOVES],
+        }
+    }
+
+    fn push(&mut self, mv: Move, score: i32) {
+        self.scores[self.moves.len()] = score;
+        self.moves.push(mv);
+    }
+
+    fn remove_at(&mut self, idx: usize) -> (Move, i32) {
+        let score = self.scores[idx];
+        self.scores[idx] = self.scores[self.moves.len() - 1];
+        (self.moves.swap_remove(idx), score)
+    }
+
+    // Selection rather than a full sort, since a cutoff usually comes early
+    fn pop_best(&mut self) -> Option<(Move, i32)> {
+        let best = (0..self.moves.len()).max_by_key(|&idx| self.scores[idx])?;
+        Some(self.remove_at(best))
+    }
+}
+
+// Yields the legal moves one at a time in search order: hash move, good
+// captures, killers, counter-move, quiets by history, then bad captures. Each
+// list is generated only once the picker gets to it, so a cutoff on an early
+// move saves generating the rest, and a cutoff on the hash move or a killer
+// needs no generation at all.
+pub struct MovePicker<'a> {
+    game: &'a Game,
+    history: Option<&'a History>,
+    stage: Stage,
+    hash_move: Move,
+    killers: [Move; 2],
+    killer_index: usize,
+    counter_move: Move,
+    // Hash move, killers and counter-move already yielded
+    tried: [Move; 4],
+    tried_len: usize,
+    skip_quiets: bool,
+    captures: Option<ScoredList>,
+    quiets: Option<ScoredList>,
+    bad_captures: ScoredList,
+    evasions: Option<ScoredList>,
+}
+
+impl<'a> MovePicker<'a> {
+    pub fn new(
+        game: &'a Game,
+        hash_move: Move,
+        killers: [Move; 2],
+        counter_move: Move,
+        history: &'a History,
+    ) -> Self {
+        let stage = if game.is_in_check() {
+            Stage::EvasionHashMove
+        } else {
+            Stage::HashMove
+        };
+        MovePicker {
+            game,
+            history: Some(history),
+            stage,
+            hash_move,
+            killers,
+            killer_index: 0,
+            counter_move,
+            tried: [Move::NULL; 4],
+            tried_len: 0,
+            skip_quiets: false,
+            captures: None,
+            quiets: None,
+            bad_captures: ScoredList::new(),
+            evasions: None,
+        }
+    }
+
+    // Captures and queen promotions only, or every evasion when in check
+    pub fn quiescence(game: &'a Game, hash_move: Move) -> Self {
+        let stage = if game.is_in_check() {
+            Stage::EvasionHashMove
+        } else {
+            Stage::HashMove
+        };
+        MovePicker {
+            game,
+            history: None,
+            stage,
+            hash_move,
+            killers: [Move::NULL; 2],
+            killer_index: 0,
+            counter_move: Move::NULL,
+            tried: [Move::NULL; 4],
+            tried_len: 0,
+            skip_quiets: true,
+            captures: None,
+            quiets: None,
+            bad_captures: ScoredList::new(),
+            evasions: None,
+        }
+    }
+
+    // Stops yielding quiet moves from here on, for late move pruning. Evasions
+    // are never skipped.
+    pub fn skip_quiets(&mut self) {
+        self.skip_quiets = true;
+    }
+
+    fn mvv_lva(&self, mv: Move) -> i32 {
+        let attacker = self
+            .game
+            .get_piece_at(mv.from())
+            .map_or(0, |p| p.piece_type() as i32);
+        let victim = if mv.is_en_passant() {
+            PIECE_VALUES[PieceType::Pawn as usize]
+        } else {
+            self.game
+                .get_piece_at(mv.to())
+                .map_or(0, |p| PIECE_VALUES[p.piece_type() as usize])
+        };
+        let promotion = mv.promotion_piece().map_or(0, |pt| {
+            PIECE_VALUES[pt as usize] - PIECE_VALUES[PieceType::Pawn as usize]
+        });
+        (victim + promotion) * 8 - attacker
+    }
+
+    // A capture is good when the exchange it starts doesn't lose material
+    fn is_good_capture(&self, mv: Move) -> bool {
+        see_ge(self.game, mv, 0)
+    }
+
+    // The hash move, killers and counter-move come from other nodes, so they
+    // are checked for legality here instead of being looked up in a list
+    fn try_special(&mut self, mv: Move) -> bool {
+        if mv.is_null() || self.tried[..self.tried_len].contains(&mv) || !is_legal(self.game, mv) {
+            return false;
+        }
+        self.tried[self.tried_len] = mv;
+        self.tried_len += 1;
+        true
+    }
+
+    // Best remaining move of a stage, skipping any already tried early
+    fn pop_untried(&mut self, list: impl Fn(&mut Self) -> &mut ScoredList) -> Option<(Move, i32)> {
+        loop {
+            let (mv, score) = list(self).pop_best()?;
+            if !self.tried[..self.tried_len].contains(&mv) {
+                return Some((mv, score));
+            }
+        }
+    }
+
+    fn history_score(&self, mv: Move) -> i32 {
+        self.history
+            .map_or(0, |history| history.get(self.game.side(), mv))
+    }
+
+    fn captures(&mut self) -> &mut ScoredList {
+        if self.captures.is_none() {
+            let mut moves = MoveList::new();
+            generate_into(self.game, GenType::Captures, &mut moves);
+            let mut list = ScoredList::new();
+            for &mv in &moves {
+                list.push(mv, self.mvv_lva(mv));
+            }
+            self.captures = Some(list);
+        }
+        self.captures.as_mut().unwrap()
+    }
+
+    fn quiets(&mut self) -> &mut ScoredList {
+        if self.quiets.is_none() {
+            let mut moves = MoveList::new();
+            generate_into(self.game, GenType::Quiets, &mut moves);
+            let mut list = ScoredList::new();
+            for &mv in &moves {
+                list.push(mv, self.history_score(mv));
+            }
+            self.quiets = Some(list);
+        }
+        self.quiets.as_mut().unwrap()
+    }
+
+    fn evasions(&mut self) -> &mut ScoredList {
+        if self.evasions.is_none() {
+            let mut moves = MoveList::new();
+            generate_into(self.game, GenType::Evasions, &mut moves);
+            let mut list = ScoredList::new();
+            for &mv in &moves {
+                // Captures of the checker go before any king walk
+                let score = if is_tactical(mv) {
+                    MAX_HISTORY + 1 + self.mvv_lva(mv)
+                } else {
+                    self.history_score(mv)
+                };
+                list.push(mv, score);
+            }
+            self.evasions = Some(list);
+        }
+        self.evasions.as_mut().unwrap()
+    }
+}
+
+impl Iterator for MovePicker<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GoodCaptures;
+                    let mv = self.hash_move;
+                    if (is_tactical(mv) || !self.skip_quiets) && self.try_special(mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::GoodCaptures => match self.pop_untried(|picker| picker.captures()) {
+                    Some((mv, score)) => {
+                        if self.is_good_capture(mv) {
+                            return Some(mv);
+                        }
+                        self.bad_captures.push(mv, score);
+                    }
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    if self.skip_quiets || self.killer_index == self.killers.len() {
+                        self.stage = Stage::CounterMove;
+                        continue;
+                    }
+                    let mv = self.killers[self.killer_index];
+                    self.killer_index += 1;
+                    if !is_tactical(mv) && self.try_special(mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::CounterMove => {
+                    self.stage = Stage::Quiets;
+                    let mv = self.counter_move;
+                    if !self.skip_quiets && !is_tactical(mv) && self.try_special(mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::Quiets => {
+                    if !self.skip_quiets
+                        && let Some((mv, _)) = self.pop_untried(|picker| picker.quiets())
+                    {
+                        return Some(mv);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => match self.pop_untried(|picker| &mut picker.bad_captures) {
+                    Some((mv, _)) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::EvasionHashMove => {
+                    self.stage = Stage::Evasions;
+                    let mv = self.hash_move;
+                    if self.try_special(mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::Evasions => match self.pop_untried(|picker| picker.evasions()) {
+                    Some((mv, _)) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod movepick_test {
+    use arena::{board::square, fen::START_FEN, moves::MoveFlag};
+
+    use super::*;
+    use crate::movegen::generate_legal;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn sorted(moves: &[Move]) -> Vec<u16> {
+        let mut raw: Vec<u16> = moves.iter().map(|mv| mv.raw()).collect();
+        raw.sort();
+        raw
+    }
+
+    #[test]
+    fn test_yields_every_legal_move_once() {
+        let history = History::new();
+        let foreign = [
+            Move::quiet(square("a1"), square("a5")),
+            Move::capture(square("h8"), square("h1")),
+            Move::new(square("e1"), square("g1"), MoveFlag::KingCastle),
+        ];
+        for fen in [
+            START_FEN,
+            KIWIPETE,
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4r2k/8/8/8/7b/8/3Q4/4K3 w - - 0 1",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            let legal = generate_legal(&game);
+            // Hash and killers that are legal here as well as ones from elsewhere
+            let hash = legal.first().copied().unwrap_or(Move::NULL);
+            let killers = [legal.last().copied().unwrap_or(Move::NULL), foreign[0]];
+            let picked: Vec<Move> =
+                MovePicker::new(&game, hash, killers, foreign[2], &history).collect();
+            assert_eq!(sorted(&picked), sorted(&legal), "{fen}");
+            if !hash.is_null() {
+                assert_eq!(picked[0], hash);
+            }
+
+            let picked: Vec<Move> = MovePicker::new(
+                &game,
+                foreign[1],
+                foreign[..2].try_into().unwrap(),
+                foreign[0],
+                &history,
+            )
+            .collect();
+            assert_eq!(sorted(&picked), sorted(&legal), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_stage_order() {
+        // Nxd5 wins a rook, Qxb7 gives the queen for a defended pawn
+        let game = Game::from_fen("k7/1p6/p7/3r4/8/2N5/8/1Q4K1 w - - 0 1").unwrap();
+        let mut history = History::new();
+        let favourite = Move::quiet(square("g1"), square("h2"));
+        history.update(game.side(), favourite, 1000);
+
+        let killer = Move::quiet(square("b1"), square("b2"));
+        let counter = Move::quiet(square("c3"), square("e4"));
+        let hash = Move::quiet(square("g1"), square("f1"));
+        let picked: Vec<Move> =
+            MovePicker::new(&game, hash, [killer, Move::NULL], counter, &history).collect();
+
+        assert_eq!(picked[0], hash);
+        assert_eq!(picked[1], Move::capture(square("c3"), square("d5")));
+        assert_eq!(picked[2], killer);
+        assert_eq!(picked[3], counter);
+        assert_eq!(picked[4], favourite);
+        assert_eq!(
+            picked.last(),
+            Some(&Move::capture(square("b1"), square("b7")))
+        );
+        assert_eq!(picked.len(), generate_legal(&game).len());
+    }
+
+    #[test]
+    fn test_captures_by_mvv_lva() {
+        let game = Game::from_fen(KIWIPETE).unwrap();
+        let picked: Vec<Move> = MovePicker::quiescence(&game, Move::NULL).collect();
+        assert!(picked.iter().all(|&mv| is_tactical(mv)));
+        assert_eq!(picked.len(), 8);
+        // The bishop is the most valuable victim. Only the pawn takes are safe
+        // after it, then the captures into defended squares, knight first.
+        assert_eq!(picked[0], Move::capture(square("e2"), square("a6")));
+        assert_eq!(picked[3], Move::capture(square("f3"), square("f6")));
+        assert_eq!(picked[7], Move::capture(square("f3"), square("h3")));
+    }
+
+    #[test]
+    fn test_generates_lazily() {
+        let game = Game::from_fen(KIWIPETE).unwrap();
+        let history = History::new();
+        let hash = Move::capture(square("e2"), square("a6"));
+        let mut picker = MovePicker::new(&game, hash, [Move::NULL; 2], Move::NULL, &history);
+        assert_eq!(picker.next(), Some(hash));
+        assert!(picker.captures.is_none());
+        assert!(picker.quiets.is_none());
+        picker.next();
+        assert!(picker.quiets.is_none());
+        while picker.stage < Stage::Killers {
+            picker.next();
+        }
+        assert!(picker.captures.as_ref().unwrap().moves.is_empty());
+    }
+
+    #[test]
+    fn test_evasions() {
+        let game = Game::from_fen("4k3/8/8/8/8/R3q3/8/4K3 w - - 0 1").unwrap();
+        let history = History::new();
+        let legal = generate_legal(&game);
+        let picked: Vec<Move> = MovePicker::quiescence(&game, Move::NULL).collect();
+        assert_eq!(sorted(&picked), sorted(&legal));
+        // Taking the checking queen comes first
+        assert_eq!(picked[0], Move::capture(square("a3"), square("e3")));
+
+        let mut picker = MovePicker::new(&game, Move::NULL, [Move::NULL; 2], Move::NULL, &history);
+        picker.skip_quiets();
+        assert_eq!(picker.count(), legal.len());
+    }
+
+    #[test]
+    fn test_history_update() {
+        let mut history = History::new();
+        let mv = Move::quiet(square("e2"), square("e4"));
+        for _ in 0..100 {
+            history.update(Color::White, mv, 2000);
+        }
+        assert!(history.get(Color::White, mv) <= MAX_HISTORY);
+        assert!(history.get(Color::White, mv) > MAX_HISTORY / 2);
+        assert_eq!(history.get(Color::Black, mv), 0);
+        history.clear();
+        assert_eq!(history.get(Color::White, mv), 0);
+    }
+}
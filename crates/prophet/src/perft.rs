@@ -0,0 +1,389 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use arena::{game::Game, moves::Move};
+
+use crate::movegen::generate_legal;
+
+// Number of leaf nodes `depth` plies below the current position
+pub fn perft(game: &mut Game, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = generate_legal(game);
+    // Legal generation means the last ply doesn't need to be played out
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for &mv in &moves {
+        game.make_move(mv);
+        nodes += perft(game, depth - 1);
+        game.unmake_move();
+    }
+    nodes
+}
+
+// Perft split by root move, for finding which move a generator bug hides under
+pub fn divide(game: &mut Game, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves = generate_legal(game);
+    let mut counts = Vec::with_capacity(moves.len());
+    for &mv in &moves {
+        game.make_move(mv);
+        counts.push((mv, perft(game, depth - 1)));
+        game.unmake_move();
+    }
+    counts
+}
+
+// Subtree counts keyed by position and depth, shared between perft threads.
+// Each slot holds key ^ data next to data, so a slot torn by two threads
+// writing at once fails the key check rather than handing back a wrong count.
+pub struct PerftTable {
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl PerftTable {
+    // Rounded down to a power of two number of slots
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes * 1024 * 1024 / 16).max(1);
+        let len = 1 << wanted.ilog2();
+        PerftTable {
+            slots: (0..len)
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &[AtomicU64; 2] {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    // Depth goes in the low byte, so the same position at another depth misses
+    fn probe(&self, key: u64, depth: u32) -> Option<u64> {
+        let [check, data] = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+        let check = check.load(Ordering::Relaxed);
+        (check ^ data == key && data & 0xFF == depth as u64).then_some(data >> 8)
+    }
+
+    fn store(&self, key: u64, depth: u32, nodes: u64) {
+        let data = (nodes << 8) | depth as u64;
+        let [check, slot] = self.slot(key);
+        check.store(key ^ data, Ordering::Relaxed);
+        slot.store(data, Ordering::Relaxed);
+    }
+}
+
+// Perft with subtree counts cached by Zobrist key, so transpositions are only
+// counted once
+pub fn perft_hashed(game: &mut Game, depth: u32, table: &PerftTable) -> u64 {
+    if depth <= 1 {
+        return perft(game, depth);
+    }
+    if let Some(nodes) = table.probe(game.key(), depth) {
+        return nodes;
+    }
+    let moves = generate_legal(game);
+    let mut nodes = 0;
+    for &mv in &moves {
+        game.make_move(mv);
+        nodes += perft_hashed(game, depth - 1, table);
+        game.unmake_move();
+    }
+    table.store(game.key(), depth, nodes);
+    nodes
+}
+
+#[derive(Debug, Clone)]
+pub struct PerftReport {
+    pub nodes: u64,
+    // Count under each root move, in generation order
+    pub moves: Vec<(Move, u64)>,
+    pub elapsed: Duration,
+}
+
+impl PerftReport {
+    pub fn nps(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            (self.nodes as f64 / secs) as u64
+        } else {
+            0
+        }
+    }
+}
+
+impl fmt::Display for PerftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes {} time {:.3}s nps {}",
+            self.nodes,
+            self.elapsed.as_secs_f64(),
+            self.nps()
+        )
+    }
+}
+
+// Splits the root moves over `threads` workers, each playing on its own copy of
+// the game. With a table all workers share it.
+pub fn perft_parallel(
+    game: &Game,
+    depth: u32,
+    threads: usize,
+    table: Option<&PerftTable>,
+) -> PerftReport {
+    let start = Instant::now();
+    if depth == 0 {
+        return PerftReport {
+            nodes: 1,
+            moves: Vec::new(),
+            elapsed: start.elapsed(),
+        };
+    }
+    let root = generate_legal(game);
+    let next = AtomicUsize::new(0);
+
+    let mut counts = vec![0; root.len()];
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, root.len().max(1)))
+            .map(|_| {
+                let mut game = game.clone();
+                let (root, next) = (&root, &next);
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&mv) = root.get(idx) else {
+                            return done;
+                        };
+                        game.make_move(mv);
+                        let nodes = match table {
+                            Some(table) => perft_hashed(&mut game, depth - 1, table),
+                            None => perft(&mut game, depth - 1),
+                        };
+                        game.unmake_move();
+                        done.push((idx, nodes));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (idx, nodes) in worker.join().expect("perft worker panicked") {
+                counts[idx] = nodes;
+            }
+        }
+    });
+
+    PerftReport {
+        nodes: counts.iter().sum(),
+        moves: root.iter().copied().zip(counts).collect(),
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod perft_test {
+    use arena::{fen::START_FEN, variant::Variant};
+
+    use super::*;
+
+    // Reference positions and node counts from the chessprogramming wiki
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn check(fen: &str, expected: &[u64]) {
+        check_variant(fen, Variant::Standard, expected);
+    }
+
+    fn check_variant(fen: &str, variant: Variant, expected: &[u64]) {
+        let mut game = Game::from_variant_fen(fen, variant).expect("valid FEN");
+        for (depth, &nodes) in expected.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            assert_eq!(perft(&mut game, depth), nodes, "{fen} at depth {depth}");
+        }
+        let start = Game::from_variant_fen(fen, variant).unwrap();
+        assert_eq!(game.to_fen(), start.to_fen());
+        assert_eq!(game.key(), start.key());
+    }
+
+    #[test]
+    fn test_perft_shallow() {
+        check(START_FEN, &[20, 400, 8902]);
+        check(KIWIPETE, &[48, 2039, 97862]);
+        check(POSITION_3, &[14, 191, 2812, 43238]);
+        check(POSITION_4, &[6, 264, 9467]);
+        check(POSITION_4_MIRRORED, &[6, 264, 9467]);
+        check(POSITION_5, &[44, 1486, 62379]);
+        check(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn test_perft_chess960() {
+        check(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12189],
+        );
+        check(
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            &[21, 807, 18002],
+        );
+    }
+
+    #[test]
+    fn test_perft_variants() {
+        // Reference counts for antichess from the start position
+        check_variant(START_FEN, Variant::Antichess, &[20, 400, 8067]);
+        // Too early for a king on the hill or a third check to cut anything off
+        check_variant(START_FEN, Variant::KingOfTheHill, &[20, 400, 8902]);
+        check_variant(START_FEN, Variant::ThreeCheck, &[20, 400, 8902]);
+        check_variant(KIWIPETE, Variant::ThreeCheck, &[48, 2039]);
+
+        // Kd4 and Ke4 win on the spot, leaving black nothing to play
+        let fen = "8/8/8/8/8/3K4/8/7k w - - 0 1";
+        check(fen, &[8, 24]);
+        check_variant(fen, Variant::KingOfTheHill, &[8, 18]);
+        // Ra8+ is the third check
+        let fen = "7k/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0";
+        check(fen, &[15, 42]);
+        check_variant(fen, Variant::ThreeCheck, &[15, 40]);
+    }
+
+    #[test]
+    fn test_divide() {
+        let mut game = Game::new();
+        let counts = divide(&mut game, 3);
+        assert_eq!(counts.len(), 20);
+        assert_eq!(counts.iter().map(|(_, n)| n).sum::<u64>(), 8902);
+
+        let e2e4 = counts
+            .iter()
+            .find(|(mv, _)| mv.to_string() == "e2e4")
+            .unwrap();
+        assert_eq!(e2e4.1, 600);
+        assert!(divide(&mut game, 0).is_empty());
+    }
+
+    #[test]
+    fn test_hashed_and_parallel_match() {
+        let table = PerftTable::new(4);
+        for (fen, depth, nodes) in [
+            (KIWIPETE, 3, 97862),
+            (POSITION_3, 4, 43238),
+            (POSITION_4, 3, 9467),
+            (POSITION_5, 3, 62379),
+        ] {
+            let mut game = Game::from_fen(fen).unwrap();
+            assert_eq!(perft_hashed(&mut game, depth, &table), nodes, "{fen}");
+            // Second time round the answer comes from the table
+            assert_eq!(perft_hashed(&mut game, depth, &table), nodes, "{fen}");
+
+            let report = perft_parallel(&game, depth, 4, None);
+            assert_eq!(report.nodes, nodes, "{fen}");
+            assert_eq!(report.moves, divide(&mut game, depth));
+
+            let report = perft_parallel(&game, depth, 3, Some(&PerftTable::new(1)));
+            assert_eq!(report.nodes, nodes, "{fen}");
+        }
+
+        let game = Game::new();
+        assert_eq!(perft_parallel(&game, 0, 2, None).nodes, 1);
+        assert_eq!(perft_parallel(&game, 1, 64, None).nodes, 20);
+        let checkmated = Game::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(perft_parallel(&checkmated, 3, 2, None).nodes, 0);
+    }
+
+    #[test]
+    fn test_report() {
+        let report = PerftReport {
+            nodes: 3_000_000,
+            moves: Vec::new(),
+            elapsed: Duration::from_millis(1500),
+        };
+        assert_eq!(report.nps(), 2_000_000);
+        assert_eq!(report.to_string(), "nodes 3000000 time 1.500s nps 2000000");
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_parallel_deep() {
+        let table = PerftTable::new(64);
+        let game = Game::from_fen(KIWIPETE).unwrap();
+        assert_eq!(perft_parallel(&game, 5, 4, Some(&table)).nodes, 193690690);
+        let game = Game::from_fen(POSITION_3).unwrap();
+        assert_eq!(perft_parallel(&game, 7, 4, Some(&table)).nodes, 178633661);
+        let game = Game::new();
+        assert_eq!(perft_parallel(&game, 7, 4, Some(&table)).nodes, 3195901860);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_start_deep() {
+        check(START_FEN, &[20, 400, 8902, 197281, 4865609, 119060324]);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_variants_deep() {
+        check_variant(
+            START_FEN,
+            Variant::Antichess,
+            &[20, 400, 8067, 153299, 2732672],
+        );
+        check_variant(
+            START_FEN,
+            Variant::KingOfTheHill,
+            &[20, 400, 8902, 197281, 4865609],
+        );
+        check_variant(
+            START_FEN,
+            Variant::ThreeCheck,
+            &[20, 400, 8902, 197281, 4865609],
+        );
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_kiwipete_deep() {
+        check(KIWIPETE, &[48, 2039, 97862, 4085603, 193690690]);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_position_3_deep() {
+        check(POSITION_3, &[14, 191, 2812, 43238, 674624, 11030083]);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_position_4_deep() {
+        check(POSITION_4, &[6, 264, 9467, 422333, 15833292]);
+        check(POSITION_4_MIRRORED, &[6, 264, 9467, 422333, 15833292]);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_position_5_deep() {
+        check(POSITION_5, &[44, 1486, 62379, 2103487, 89941194]);
+    }
+
+    #[test]
+    #[ignore = "computationally heavier"]
+    fn test_perft_position_6_deep() {
+        check(POSITION_6, &[46, 2079, 89890, 3894594, 164075551]);
+    }
+}
@@ -0,0 +1,363 @@
+use arena::{
+    game::Game,
+    moves::Move,
+    piece::{Color, PieceType},
+    variant::Variant,
+};
+
+use crate::{
+    movegen::generate_legal,
+    movepick::{History, MAX_HISTORY, MovePicker, PIECE_VALUES},
+    see::see_ge,
+};
+
+const MATE: i32 = 100_000;
+const INFINITY: i32 = MATE + 1;
+
+// Any source of book moves, e.g. a Polyglot reader or a repertoire file.
+// Weights are relative, typically how often the move was played.
+pub trait OpeningBook {
+    fn entries(&self, game: &Game) -> Vec<(Move, u32)>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PredictionConfig {
+    // Plies searched below each candidate reply
+    pub depth: u32,
+    // Centipawns per unit of softmax logit. Lower trusts the search more.
+    pub temperature: f64,
+    // Centipawns added for a move at the top of the history table
+    pub history_bonus: f64,
+    // Share of the probability taken from the book when it knows the position
+    pub book_weight: f64,
+}
+
+impl Default for PredictionConfig {
+    fn default() -> Self {
+        PredictionConfig {
+            depth: 2,
+            temperature: 80.0,
+            history_bonus: 40.0,
+            book_weight: 0.7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub mv: Move,
+    pub probability: f64,
+    // Search score of the reply in centipawns for the side playing it
+    pub score: i32,
+}
+
+// Ranks the moves of the side to move by how likely they are to be played,
+// most likely first. Probabilities add up to one; empty when there is no legal
+// move.
+pub fn predict_replies(
+    game: &Game,
+    history: Option<&History>,
+    book: Option<&dyn OpeningBook>,
+    config: &PredictionConfig,
+) -> Vec<Prediction> {
+    let mut game = game.clone();
+    let empty = History::new();
+    let history = history.unwrap_or(&empty);
+    let side = game.side();
+
+    let moves: Vec<Move> =
+        MovePicker::new(&game, Move::NULL, [Move::NULL; 2], Move::NULL, history).collect();
+    let mut predictions: Vec<Prediction> = moves
+        .into_iter()
+        .map(|mv| {
+            game.make_move(mv);
+            let score = -search(&mut game, config.depth, -INFINITY, INFINITY, 1, history);
+            game.unmake_move();
+            Prediction {
+                mv,
+                probability: 0.0,
+                score,
+            }
+        })
+        .collect();
+    if predictions.is_empty() {
+        return predictions;
+    }
+
+    let temperature = config.temperature.max(1.0);
+    let logits: Vec<f64> = predictions
+        .iter()
+        .map(|p| {
+            let bonus = history.get(side, p.mv) as f64 / MAX_HISTORY as f64 * config.history_bonus;
+            (p.score as f64 + bonus) / temperature
+        })
+        .collect();
+    let max = logits.iter().copied().fold(f64::MIN, f64::max);
+    let softmax = normalize(logits.iter().map(|&logit| (logit - max).exp()));
+
+    let book_entries = book.map(|book| book.entries(&game)).unwrap_or_default();
+    let book_total: u64 = book_entries
+        .iter()
+        .filter(|(mv, _)| predictions.iter().any(|p| p.mv == *mv))
+        .map(|&(_, weight)| weight as u64)
+        .sum();
+    let book_weight = if book_total > 0 {
+        config.book_weight.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    for (prediction, search_share) in predictions.iter_mut().zip(softmax) {
+        let in_book = book_entries
+            .iter()
+            .filter(|(mv, _)| *mv == prediction.mv)
+            .map(|&(_, weight)| weight as u64)
+            .sum::<u64>();
+        let book_share = if book_total > 0 {
+            in_book as f64 / book_total as f64
+        } else {
+            0.0
+        };
+        prediction.probability = (1.0 - book_weight) * search_share + book_weight * book_share;
+    }
+    predictions.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+    predictions
+}
+
+fn normalize(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let values: Vec<f64> = values.collect();
+    let total: f64 = values.iter().sum();
+    values.into_iter().map(|v| v / total).collect()
+}
+
+fn material(game: &Game) -> i32 {
+    [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .into_iter()
+    .map(|pt| {
+        let balance = game.pieces(pt, Color::White).count_ones() as i32
+            - game.pieces(pt, Color::Black).count_ones() as i32;
+        balance * PIECE_VALUES[pt as usize]
+    })
+    .sum()
+}
+
+// Incremental tapered score when the evaluator has set tables up, material
+// otherwise, from the side to move's point of view. Antichess is won by losing
+// material, and the tables are tuned for chess, so it counts material the other
+// way round.
+fn evaluate(game: &Game) -> i32 {
+    let white = if game.variant == Variant::Antichess {
+        -material(game)
+    } else {
+        game.tapered_score().unwrap_or_else(|| material(game))
+    };
+    match game.side() {
+        Color::White => white,
+        Color::Black => -white,
+    }
+}
+
+fn search(
+    game: &mut Game,
+    depth: u32,
+    mut alpha: i32,
+    beta: i32,
+    ply: i32,
+    history: &History,
+) -> i32 {
+    if depth == 0 {
+        return quiescence(game, alpha, beta, ply);
+    }
+    // The picker borrows the game, so take the ordered moves out first
+    let moves: Vec<Move> =
+        MovePicker::new(game, Move::NULL, [Move::NULL; 2], Move::NULL, history).collect();
+    if moves.is_empty() {
+        return terminal_score(game, ply);
+    }
+    for mv in moves {
+        game.make_move(mv);
+        let score = -search(game, depth - 1, -beta, -alpha, ply + 1, history);
+        game.unmake_move();
+        if score >= beta {
+            return score;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+fn quiescence(game: &mut Game, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+    if game.variant_goal_reached() {
+        return terminal_score(game, ply);
+    }
+    // In check there is no standing pat, every evasion gets searched
+    let in_check = game.is_in_check();
+    if !in_check {
+        let stand_pat = evaluate(game);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+    }
+    // Captures that lose material can't raise alpha past the stand pat, except
+    // in Antichess where losing material is the point
+    let antichess = game.variant == Variant::Antichess;
+    let moves: Vec<Move> = MovePicker::quiescence(game, Move::NULL)
+        .filter(|&mv| in_check || antichess || see_ge(game, mv, 0))
+        .collect();
+    // Only captures were picked, so Antichess has to look for quiet moves too
+    if moves.is_empty() && (in_check || antichess && generate_legal(game).is_empty()) {
+        return terminal_score(game, ply);
+    }
+    for mv in moves {
+        game.make_move(mv);
+        let score = -quiescence(game, -beta, -alpha, ply + 1);
+        game.unmake_move();
+        if score >= beta {
+            return score;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+// Score of a position with no moves left, for the side to move
+fn terminal_score(game: &Game, ply: i32) -> i32 {
+    // The side that just moved reached the hill or gave the third check
+    if game.variant_goal_reached() {
+        return -MATE + ply;
+    }
+    // Having no move, with or without pieces, wins Antichess
+    if game.variant == Variant::Antichess {
+        return MATE - ply;
+    }
+    if game.is_in_check() { -MATE + ply } else { 0 }
+}
+
+#[cfg(test)]
+mod predict_test {
+    use arena::{board::square, moves::MoveFlag};
+
+    use super::*;
+
+    struct FixedBook(Vec<(Move, u32)>);
+
+    impl OpeningBook for FixedBook {
+        fn entries(&self, _game: &Game) -> Vec<(Move, u32)> {
+            self.0.clone()
+        }
+    }
+
+    fn assert_distribution(predictions: &[Prediction]) {
+        let total: f64 = predictions.iter().map(|p| p.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(
+            predictions
+                .windows(2)
+                .all(|w| w[0].probability >= w[1].probability)
+        );
+    }
+
+    #[test]
+    fn test_takes_the_hanging_queen() {
+        // Black just left the queen en prise to the knight
+        let game =
+            Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/4P2q/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let predictions = predict_replies(&game, None, None, &PredictionConfig::default());
+        assert_distribution(&predictions);
+        assert_eq!(predictions[0].mv, Move::capture(square("f3"), square("h4")));
+        assert!(predictions[0].probability > 0.9);
+    }
+
+    #[test]
+    fn test_finds_mate() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let predictions = predict_replies(&game, None, None, &PredictionConfig::default());
+        assert_eq!(predictions[0].mv, Move::quiet(square("a1"), square("a8")));
+        assert!(predictions[0].score > MATE / 2);
+
+        let mated = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(predict_replies(&mated, None, None, &PredictionConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_finds_mate_at_the_horizon() {
+        // With no plies below the reply, only quiescence sees the mate
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let config = PredictionConfig {
+            depth: 0,
+            ..PredictionConfig::default()
+        };
+        let predictions = predict_replies(&game, None, None, &config);
+        assert_eq!(predictions[0].mv, Move::quiet(square("a1"), square("a8")));
+        assert_eq!(predictions[0].score, MATE - 1);
+    }
+
+    #[test]
+    fn test_variant_terminal_scores() {
+        let config = PredictionConfig::default();
+
+        let game = Game::from_variant_fen("4k3/8/8/8/8/2K5/8/8 w - - 0 1", Variant::KingOfTheHill)
+            .unwrap();
+        let predictions = predict_replies(&game, None, None, &config);
+        assert_eq!(predictions[0].mv, Move::quiet(square("c3"), square("d4")));
+        assert_eq!(predictions[0].score, MATE - 1);
+
+        // Walking into the rook gives the last piece away
+        let game =
+            Game::from_variant_fen("8/8/8/r7/4K3/8/8/8 w - - 0 1", Variant::Antichess).unwrap();
+        let predictions = predict_replies(&game, None, None, &config);
+        assert_eq!(predictions[0].mv.to() / 8, 4);
+        assert_eq!(predictions[0].score, MATE - 2);
+    }
+
+    #[test]
+    fn test_antichess_gives_material_away() {
+        // 1. e3 e6
+        let game = Game::from_variant_fen(
+            "rnbqkbnr/pppp1ppp/4p3/8/8/4P3/PPPP1PPP/RNBQKBNR w - - 0 2",
+            Variant::Antichess,
+        )
+        .unwrap();
+        let predictions = predict_replies(&game, None, None, &PredictionConfig::default());
+        assert_distribution(&predictions);
+        // Black has to take the bishop, and no one runs out of pieces in sight
+        assert_eq!(predictions[0].mv, Move::quiet(square("f1"), square("a6")));
+        assert!(predictions[0].score > 0 && predictions[0].score < MATE / 2);
+    }
+
+    #[test]
+    fn test_book_and_history() {
+        let game = Game::new();
+        let config = PredictionConfig::default();
+        let flat = predict_replies(&game, None, None, &config);
+        assert_distribution(&flat);
+        assert_eq!(flat.len(), 20);
+
+        let d4 = Move::new(square("d2"), square("d4"), MoveFlag::DoublePush);
+        let book = FixedBook(vec![
+            (d4, 3),
+            (Move::quiet(square("a1"), square("a5")), 100),
+        ]);
+        let booked = predict_replies(&game, None, Some(&book), &config);
+        assert_distribution(&booked);
+        // The illegal book entry is ignored, so d4 gets the whole book share
+        assert_eq!(booked[0].mv, d4);
+        assert!(booked[0].probability > config.book_weight);
+
+        let h3 = Move::quiet(square("h2"), square("h3"));
+        let mut history = History::new();
+        history.update(Color::White, h3, MAX_HISTORY);
+        let flat_h3 = flat.iter().find(|p| p.mv == h3).unwrap().probability;
+        let boosted = predict_replies(&game, Some(&history), None, &config);
+        let boosted_h3 = boosted.iter().find(|p| p.mv == h3).unwrap().probability;
+        assert!(boosted_h3 > flat_h3);
+    }
+}
@@ -0,0 +1,147 @@
+use arena::{
+    board::Board,
+    game::Game,
+    moves::{Move, MoveFlag},
+    piece::PieceType,
+};
+
+use crate::{movegen::generate_legal, movelist::MoveList};
+
+// Legal moves of the piece on `from`, promotions listed once per piece
+pub fn moves_from(game: &Game, from: u8) -> MoveList {
+    filtered(game, |mv| mv.from() == from)
+}
+
+// Legal moves landing on `to`. Castling counts as landing on the king's
+// destination square.
+pub fn moves_to(game: &Game, to: u8) -> MoveList {
+    filtered(game, |mv| mv.to() == to)
+}
+
+pub fn destinations(game: &Game, from: u8) -> Board {
+    squares(&moves_from(game, from), Move::to)
+}
+
+pub fn origins(game: &Game, to: u8) -> Board {
+    squares(&moves_to(game, to), Move::from)
+}
+
+// The pieces a pawn may become going from `from` to `to`, strongest first,
+// empty when that move isn't a legal promotion
+pub fn promotion_choices(game: &Game, from: u8, to: u8) -> Vec<PieceType> {
+    generate_legal(game)
+        .iter()
+        .filter(|mv| mv.from() == from && mv.to() == to)
+        .filter_map(|mv| mv.promotion_piece())
+        .collect()
+}
+
+// Turns a from/to pair picked on a board into the legal move it means. A king
+// dropped on its own castling rook castles, as Chess960 GUIs expect; in Chess960
+// that is the only way to castle, since the king's destination may also be an
+// ordinary king step. Promotions need the piece, anything else ignores it.
+pub fn find_move(game: &Game, from: u8, to: u8, promotion: Option<PieceType>) -> Option<Move> {
+    generate_legal(game).iter().copied().find(|&mv| {
+        let lands = if mv.is_castle() {
+            let kingside = mv.flag() == MoveFlag::KingCastle;
+            game.castling_rook_square(game.side(), kingside) == to
+                || (!game.chess960 && mv.to() == to)
+        } else {
+            mv.to() == to
+        };
+        mv.from() == from && lands && (!mv.is_promotion() || mv.promotion_piece() == promotion)
+    })
+}
+
+fn filtered(game: &Game, keep: impl Fn(&Move) -> bool) -> MoveList {
+    let mut list = MoveList::new();
+    for &mv in generate_legal(game).iter().filter(|mv| keep(mv)) {
+        list.push(mv);
+    }
+    list
+}
+
+fn squares(moves: &MoveList, square: fn(Move) -> u8) -> Board {
+    Board::new(moves.iter().fold(0, |bits, &mv| bits | 1 << square(mv)))
+}
+
+#[cfg(test)]
+mod queries_test {
+    use arena::board::{square, square_name};
+
+    use super::*;
+
+    fn names(board: Board) -> Vec<String> {
+        board.squares().map(square_name).collect()
+    }
+
+    #[test]
+    fn test_destinations_and_origins() {
+        let game = Game::new();
+        assert_eq!(names(destinations(&game, square("e2"))), ["e3", "e4"]);
+        assert_eq!(names(destinations(&game, square("g1"))), ["f3", "h3"]);
+        assert!(destinations(&game, square("e1")).is_empty());
+        assert!(destinations(&game, square("e7")).is_empty());
+        assert!(destinations(&game, square("e4")).is_empty());
+
+        assert_eq!(names(origins(&game, square("f3"))), ["g1", "f2"]);
+        assert!(origins(&game, square("d5")).is_empty());
+        assert_eq!(moves_from(&game, square("b1")).len(), 2);
+        assert_eq!(moves_to(&game, square("c3")).len(), 2);
+
+        // A pinned knight goes nowhere and doesn't reach anything
+        let game = Game::from_fen("4r2k/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        assert!(destinations(&game, square("e2")).is_empty());
+        assert_eq!(names(origins(&game, square("d2"))), ["e1"]);
+    }
+
+    #[test]
+    fn test_promotion_choices() {
+        let game = Game::from_fen("1n5k/P7/8/8/8/8/8/7K w - - 0 1").unwrap();
+        let all = vec![
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ];
+        assert_eq!(promotion_choices(&game, square("a7"), square("a8")), all);
+        assert_eq!(promotion_choices(&game, square("a7"), square("b8")), all);
+        assert!(promotion_choices(&game, square("h1"), square("h2")).is_empty());
+        assert_eq!(moves_from(&game, square("a7")).len(), 8);
+
+        let mv = find_move(&game, square("a7"), square("b8"), Some(PieceType::Knight)).unwrap();
+        assert_eq!(
+            mv,
+            Move::promotion(square("a7"), square("b8"), PieceType::Knight, true)
+        );
+        assert_eq!(find_move(&game, square("a7"), square("a8"), None), None);
+    }
+
+    #[test]
+    fn test_find_move_castling() {
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let castle = find_move(&game, square("e1"), square("g1"), None).unwrap();
+        assert!(castle.is_castle());
+        assert_eq!(
+            find_move(&game, square("e1"), square("h1"), None),
+            Some(castle)
+        );
+        let long = find_move(&game, square("e1"), square("a1"), None).unwrap();
+        assert_eq!(long.to(), square("c1"));
+        assert_eq!(find_move(&game, square("e1"), square("e3"), None), None);
+
+        // Chess960 king on f1 castling onto the g1 rook's side
+        let game = Game::from_fen("k7/8/8/8/8/8/8/1R3KR1 w GB - 0 1").unwrap();
+        let castle = find_move(&game, square("f1"), square("g1"), None).unwrap();
+        assert!(castle.is_castle());
+        let long = find_move(&game, square("f1"), square("b1"), None).unwrap();
+        assert_eq!(long.flag(), MoveFlag::QueenCastle);
+
+        // King on d1: c1 is both a king step and where O-O-O puts the king
+        let game = Game::from_fen("k7/8/8/8/8/8/8/R2K4 w A - 0 1").unwrap();
+        let step = find_move(&game, square("d1"), square("c1"), None).unwrap();
+        assert_eq!(step, Move::quiet(square("d1"), square("c1")));
+        let long = find_move(&game, square("d1"), square("a1"), None).unwrap();
+        assert_eq!(long.flag(), MoveFlag::QueenCastle);
+    }
+}
@@ -0,0 +1,303 @@
+use arena::{
+    board::Board,
+    game::Game,
+    moves::Move,
+    piece::{Color, PieceType},
+};
+use magician::{get_bishop_attacks, get_rook_attacks};
+
+use crate::movepick::PIECE_VALUES;
+
+const ATTACKER_ORDER: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+fn value(piece_type: PieceType) -> i32 {
+    PIECE_VALUES[piece_type as usize]
+}
+
+// Material the capture on `to` wins before any recapture, and the value of what
+// then stands on the square
+fn initial_exchange(game: &Game, mv: Move) -> (i32, i32) {
+    let mover = game
+        .get_piece_at(mv.from())
+        .map_or(PieceType::Pawn, |p| p.piece_type());
+    let captured = if mv.is_en_passant() {
+        value(PieceType::Pawn)
+    } else if mv.is_capture() {
+        game.get_piece_at(mv.to())
+            .map_or(0, |p| value(p.piece_type()))
+    } else {
+        0
+    };
+    match mv.promotion_piece() {
+        Some(promoted) => (
+            captured + value(promoted) - value(PieceType::Pawn),
+            value(promoted),
+        ),
+        None => (captured, value(mover)),
+    }
+}
+
+// Occupancy once the move is played, with every piece that attacks `to`
+// through it
+fn after_move(game: &Game, mv: Move) -> (u64, u64) {
+    let mut occupancy = game.all_pieces().bits() & !(1 << mv.from());
+    if mv.is_en_passant() {
+        let captured = match game.side() {
+            Color::White => mv.to() - 8,
+            Color::Black => mv.to() + 8,
+        };
+        occupancy &= !(1 << captured);
+    }
+    let attackers = game.attackers_to(mv.to(), occupancy).bits() & occupancy;
+    (occupancy, attackers)
+}
+
+// Least valuable piece of `color` among the attackers
+fn least_valuable(game: &Game, attackers: u64, color: Color) -> Option<(PieceType, u8)> {
+    ATTACKER_ORDER.into_iter().find_map(|pt| {
+        let candidates = attackers & game.pieces(pt, color);
+        (candidates != 0).then(|| (pt, candidates.trailing_zeros() as u8))
+    })
+}
+
+// Sliders behind the piece that just captured now see the square
+fn xrays(game: &Game, to: u8, occupancy: u64, moved: PieceType) -> u64 {
+    let diagonal = game.pieces(PieceType::Bishop, Color::White)
+        | game.pieces(PieceType::Bishop, Color::Black)
+        | game.pieces(PieceType::Queen, Color::White)
+        | game.pieces(PieceType::Queen, Color::Black);
+    let orthogonal = game.pieces(PieceType::Rook, Color::White)
+        | game.pieces(PieceType::Rook, Color::Black)
+        | game.pieces(PieceType::Queen, Color::White)
+        | game.pieces(PieceType::Queen, Color::Black);
+    let mut found = 0;
+    if matches!(
+        moved,
+        PieceType::Pawn | PieceType::Bishop | PieceType::Queen
+    ) {
+        found |= get_bishop_attacks(to, occupancy) & diagonal;
+    }
+    if matches!(moved, PieceType::Rook | PieceType::Queen) {
+        found |= get_rook_attacks(to, occupancy) & orthogonal;
+    }
+    found & occupancy
+}
+
+// Material balance of the whole exchange the move starts on its target square,
+// both sides always recapturing with their least valuable piece and either side
+// free to stop when going on would lose more. Pins are not considered.
+pub fn see(game: &Game, mv: Move) -> i32 {
+    if mv.is_castle() || mv.is_null() {
+        return 0;
+    }
+    let to = mv.to();
+    let (first_gain, mut on_square) = initial_exchange(game, mv);
+    let (mut occupancy, mut attackers) = after_move(game, mv);
+
+    let mut gain = [0i32; 32];
+    gain[0] = first_gain;
+    let mut depth = 0;
+    let mut side = game.side().opposite();
+    while let Some((piece_type, from)) = least_valuable(game, attackers, side) {
+        // The king can only take last
+        if piece_type == PieceType::King && attackers & game.color_pieces(side.opposite()) != 0 {
+            break;
+        }
+        depth += 1;
+        gain[depth] = on_square - gain[depth - 1];
+        on_square = value(piece_type);
+        occupancy &= !(1 << from);
+        attackers = (attackers | xrays(game, to, occupancy, piece_type)) & occupancy;
+        side = side.opposite();
+    }
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+    gain[0]
+}
+
+// Whether see(mv) >= threshold, stopping as soon as the answer is settled
+pub fn see_ge(game: &Game, mv: Move, threshold: i32) -> bool {
+    if mv.is_castle() || mv.is_null() {
+        return threshold <= 0;
+    }
+    let to = mv.to();
+    let (first_gain, on_square) = initial_exchange(game, mv);
+
+    // Even keeping the whole first capture isn't enough
+    let mut swap = first_gain - threshold;
+    if swap < 0 {
+        return false;
+    }
+    // Losing the capturing piece straight back still leaves enough
+    swap = on_square - swap;
+    if swap <= 0 {
+        return true;
+    }
+
+    let (mut occupancy, mut attackers) = after_move(game, mv);
+    let mut side = game.side();
+    let mut result = true;
+    loop {
+        side = side.opposite();
+        let Some((piece_type, from)) = least_valuable(game, attackers, side) else {
+            break;
+        };
+        if piece_type == PieceType::King {
+            // Taking with the king only works when nothing can take back
+            return if attackers & game.color_pieces(side.opposite()) != 0 {
+                result
+            } else {
+                !result
+            };
+        }
+        result = !result;
+        swap = value(piece_type) - swap;
+        if swap < result as i32 {
+            break;
+        }
+        occupancy &= !(1 << from);
+        attackers = (attackers | xrays(game, to, occupancy, piece_type)) & occupancy;
+    }
+    result
+}
+
+// What `by` wins by starting an exchange on `square` with its least valuable
+// attacker, zero when it has none or would lose by starting one
+pub fn square_threat(game: &Game, square: u8, by: Color) -> i32 {
+    let Some(target) = game.get_piece_at(square) else {
+        return 0;
+    };
+    if target.color() == by {
+        return 0;
+    }
+    let occupancy = game.all_pieces().bits();
+    let attackers = game.attackers_to(square, occupancy).bits();
+    let Some((_, from)) = least_valuable(game, attackers, by) else {
+        return 0;
+    };
+    // Play the exchange out from the attacker's side of the board
+    let mut from_their_side = game.clone();
+    if from_their_side.side() != by {
+        from_their_side.side_to_move = !from_their_side.side_to_move;
+    }
+    see(&from_their_side, Move::capture(from, square)).max(0)
+}
+
+pub fn threatened_squares(game: &Game, by: Color) -> Board {
+    let them = by.opposite();
+    let targets = game.color_pieces(them) & !game.pieces(PieceType::King, them);
+    let threatened = Board::new(targets)
+        .squares()
+        .filter(|&sq| square_threat(game, sq, by) > 0)
+        .fold(0, |bits, sq| bits | 1 << sq);
+    Board::new(threatened)
+}
+
+#[cfg(test)]
+mod see_test {
+    use arena::{board::square, moves::MoveFlag};
+
+    use super::*;
+    use crate::movegen::{generate_captures, generate_legal};
+
+    fn see_of(fen: &str, mv: Move) -> i32 {
+        see(&Game::from_fen(fen).unwrap(), mv)
+    }
+
+    #[test]
+    fn test_simple_exchanges() {
+        // Undefended pawn
+        let fen = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("e1"), square("e5"))), 100);
+
+        // Knight takes a pawn defended by a pawn, with a rook and a queen
+        // stacked behind on both sides
+        let fen = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("d3"), square("e5"))), -220);
+
+        // A quiet move to a safe square, then QxP, RxQ
+        let fen = "4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::quiet(square("d1"), square("d2"))), 0);
+        let fen = "3rk3/8/8/3p4/8/8/8/3QK3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("d1"), square("d5"))), -800);
+    }
+
+    #[test]
+    fn test_xrays() {
+        // The queen behind the capturing rook backs it up
+        let fen = "4r1k1/8/8/4p3/8/8/4R3/4QK2 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("e2"), square("e5"))), 100);
+        // NxP dxN BxP RxB, and only then the queen behind the bishop joins in
+        let fen = "4r1k1/8/3p4/4p3/3B4/2Q2N2/8/6K1 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("f3"), square("e5"))), -120);
+    }
+
+    #[test]
+    fn test_king_and_promotion() {
+        // The king may not recapture into a defended square
+        let fen = "8/8/8/2k5/3p4/4P3/8/3RK3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("e3"), square("d4"))), 100);
+        let fen = "8/8/8/2k5/3p4/4P3/8/4K3 w - - 0 1";
+        assert_eq!(see_of(fen, Move::capture(square("e3"), square("d4"))), 0);
+
+        // Promoting into a rook's file loses the new queen for the rook's nothing
+        let fen = "r5k1/1P6/8/8/8/8/8/6K1 w - - 0 1";
+        let promote = Move::promotion(square("b7"), square("b8"), PieceType::Queen, false);
+        assert_eq!(see_of(fen, promote), -100);
+        let take = Move::promotion(square("b7"), square("a8"), PieceType::Queen, true);
+        assert_eq!(see_of(fen, take), 1300);
+
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let ep = Move::new(square("e5"), square("d6"), MoveFlag::EnPassant);
+        assert_eq!(see_of(fen, ep), 100);
+    }
+
+    #[test]
+    fn test_see_ge_agrees_with_see() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            for &mv in &generate_legal(&game) {
+                let value = see(&game, mv);
+                for threshold in [-1000, -300, -100, -1, 0, 1, 100, 200, 300, 500, 1000] {
+                    assert_eq!(
+                        see_ge(&game, mv, threshold),
+                        value >= threshold,
+                        "{mv} threshold {threshold} see {value} in {fen}"
+                    );
+                }
+            }
+            assert!(
+                generate_captures(&game)
+                    .iter()
+                    .all(|&mv| see(&game, mv) <= initial_exchange(&game, mv).0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_square_threats() {
+        // The d5 knight is attacked by a pawn, the b7 pawn is defended enough
+        let game = Game::from_fen("1k6/1p6/8/3n4/4P3/8/1R6/4K3 w - - 0 1").unwrap();
+        assert_eq!(square_threat(&game, square("d5"), Color::White), 320);
+        assert_eq!(square_threat(&game, square("b7"), Color::White), 0);
+        assert_eq!(square_threat(&game, square("e4"), Color::Black), 0);
+        assert_eq!(
+            threatened_squares(&game, Color::White),
+            Board::new(1 << square("d5"))
+        );
+    }
+}
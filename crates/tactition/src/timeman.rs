@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use warden::clock::TimeLeft;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeConfig {
+    // Kept back on every move for the GUI and the connection
+    pub move_overhead: Duration,
+    // Moves the remaining time is spread over when the control doesn't say
+    pub horizon: u32,
+    // Most of what's left a single move may ever use
+    pub max_usage: f64,
+    // How far past the soft limit the hard limit lets a move run
+    pub hard_ratio: f64,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        TimeConfig {
+            move_overhead: Duration::from_millis(30),
+            horizon: 30,
+            max_usage: 0.8,
+            hard_ratio: 5.0,
+        }
+    }
+}
+
+// Decides how long the search spends on one move. The soft limit is checked
+// between iterations and stretches or shrinks with what the search reports;
+// the hard limit is checked inside the search and always ends it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeManager {
+    optimum: Duration,
+    maximum: Duration,
+    // Decaying count of best move changes over the last iterations
+    instability: f64,
+    previous_score: Option<i32>,
+    score_factor: f64,
+    single_reply: bool,
+}
+
+impl TimeManager {
+    pub fn new(time: &TimeLeft, config: &TimeConfig) -> Self {
+        let available = time.remaining.saturating_sub(config.move_overhead);
+        let moves_to_go = time
+            .moves_to_go
+            .unwrap_or(config.horizon)
+            .clamp(1, config.horizon.max(1));
+        let cap = available.mul_f64(config.max_usage.clamp(0.0, 1.0));
+        let optimum = (available / moves_to_go + time.increment.mul_f64(0.75)).min(cap);
+        let maximum = optimum.mul_f64(config.hard_ratio.max(1.0)).min(cap);
+        TimeManager {
+            optimum,
+            maximum,
+            instability: 0.0,
+            previous_score: None,
+            score_factor: 1.0,
+            single_reply: false,
+        }
+    }
+
+    // With only one legal move there's nothing to think about
+    pub fn set_single_reply(&mut self) {
+        self.single_reply = true;
+    }
+
+    // Feeds back a finished iteration: whether it changed the best move, and
+    // its score for the side to move in centipawns
+    pub fn update(&mut self, best_move_changed: bool, score: i32) {
+        self.instability = self.instability / 2.0 + if best_move_changed { 1.0 } else { 0.0 };
+        // A falling score buys time to find a way out, a rising one saves some
+        if let Some(previous) = self.previous_score {
+            let drop = (previous - score) as f64;
+            self.score_factor = (1.0 + drop / 200.0).clamp(0.75, 1.75);
+        }
+        self.previous_score = Some(score);
+    }
+
+    pub fn soft_limit(&self) -> Duration {
+        if self.single_reply {
+            return Duration::ZERO;
+        }
+        let instability = 1.0 + self.instability.min(1.0);
+        self.optimum
+            .mul_f64(instability * self.score_factor)
+            .min(self.maximum)
+    }
+
+    pub fn hard_limit(&self) -> Duration {
+        self.maximum
+    }
+
+    // Whether to start another iteration
+    pub fn should_stop(&self, elapsed: Duration) -> bool {
+        elapsed >= self.soft_limit()
+    }
+
+    // Whether to abort the iteration in progress
+    pub fn out_of_time(&self, elapsed: Duration) -> bool {
+        elapsed >= self.maximum
+    }
+}
+
+#[cfg(test)]
+mod timeman_test {
+    use super::*;
+
+    fn time_left(remaining_ms: u64, increment_ms: u64, moves_to_go: Option<u32>) -> TimeLeft {
+        TimeLeft {
+            remaining: Duration::from_millis(remaining_ms),
+            increment: Duration::from_millis(increment_ms),
+            moves_to_go,
+        }
+    }
+
+    #[test]
+    fn test_base_limits() {
+        let config = TimeConfig::default();
+        // A minute, spread over thirty moves after the overhead
+        let manager = TimeManager::new(&time_left(60_030, 0, None), &config);
+        assert_eq!(manager.soft_limit(), Duration::from_secs(2));
+        assert_eq!(manager.hard_limit(), Duration::from_secs(10));
+
+        let with_increment = TimeManager::new(&time_left(60_030, 1000, None), &config);
+        assert_eq!(with_increment.soft_limit(), Duration::from_millis(2750));
+
+        // Last move before the control: plenty, but never the whole clock
+        let last = TimeManager::new(&time_left(10_030, 0, Some(1)), &config);
+        assert_eq!(last.hard_limit(), Duration::from_secs(8));
+        assert!(last.soft_limit() <= last.hard_limit());
+
+        // Less than the overhead left: move at once
+        let flagging = TimeManager::new(&time_left(20, 0, None), &config);
+        assert!(flagging.out_of_time(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_adjustments() {
+        let config = TimeConfig::default();
+        let base = TimeManager::new(&time_left(60_030, 0, None), &config);
+
+        let mut unstable = base.clone();
+        unstable.update(true, 20);
+        unstable.update(true, 20);
+        assert!(unstable.soft_limit() > base.soft_limit());
+        // Settles down again once the best move stops changing
+        for _ in 0..10 {
+            unstable.update(false, 20);
+        }
+        assert!(unstable.soft_limit() < base.soft_limit().mul_f64(1.01));
+
+        let mut dropping = base.clone();
+        dropping.update(false, 50);
+        dropping.update(false, -100);
+        assert!(dropping.soft_limit() > base.soft_limit());
+        let mut rising = base.clone();
+        rising.update(false, 0);
+        rising.update(false, 200);
+        assert!(rising.soft_limit() < base.soft_limit());
+
+        let mut forced = base.clone();
+        forced.set_single_reply();
+        assert!(forced.should_stop(Duration::ZERO));
+        assert!(!forced.out_of_time(Duration::ZERO));
+        assert_eq!(forced.hard_limit(), base.hard_limit());
+    }
+}
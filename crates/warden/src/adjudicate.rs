@@ -0,0 +1,242 @@
+use arena::{game::Game, moves::Move, piece::Color};
+use prophet::legality::is_legal;
+
+use crate::outcome::{Outcome, Termination};
+
+// Scores are in centipawns from the point of view of the engine reporting them
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResignRule {
+    // Both engines must agree one side is ahead by at least this much
+    pub score: i32,
+    // For this many moves of each engine in a row, never fewer than one
+    pub moves: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRule {
+    // Neither engine may see more than this either way
+    pub score: i32,
+    // Moves of each engine in a row, never fewer than one
+    pub moves: u32,
+    // Move number from which the rule starts counting
+    pub after_move: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdjudicationConfig {
+    pub resign: Option<ResignRule>,
+    pub draw: Option<DrawRule>,
+    // Moves by each side after which the game is drawn
+    pub max_moves: Option<u32>,
+}
+
+// Watches the moves and scores of an engine match as they come in and calls
+// the game once the configured rules say it is decided
+pub struct Adjudicator {
+    config: AdjudicationConfig,
+    game: Game,
+    plies: u32,
+    // Reports in a row with white ahead (positive) or behind (negative) by the
+    // resign score
+    resign_streak: i32,
+    draw_streak: u32,
+}
+
+impl Adjudicator {
+    pub fn new(config: AdjudicationConfig, start: &Game) -> Self {
+        Adjudicator {
+            config,
+            game: start.clone(),
+            plies: 0,
+            resign_streak: 0,
+            draw_streak: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Feeds the next move with the score its engine gave for it. A move without
+    // a score, e.g. from a book, breaks every streak. The move is played as it
+    // is, so the caller must have checked it is legal.
+    pub fn report(&mut self, mv: Move, score: Option<i32>) -> Option<Outcome> {
+        debug_assert!(is_legal(&self.game, mv), "illegal move {mv}");
+        let mover = self.game.side();
+        let move_number = self.game.fullmove_number;
+        self.game.make_move(mv);
+        self.plies += 1;
+
+        let white_score = score.map(|score| match mover {
+            Color::White => score,
+            Color::Black => -score,
+        });
+        self.count_resign(white_score);
+        self.count_draw(white_score, move_number);
+
+        if let Some(rule) = self.config.resign
+            && self.resign_streak.unsigned_abs() >= 2 * rule.moves.max(1)
+        {
+            let winner = if self.resign_streak > 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            return Some(Outcome::win(winner, Termination::Adjudication));
+        }
+        if let Some(rule) = self.config.draw
+            && self.draw_streak >= 2 * rule.moves.max(1)
+        {
+            return Some(Outcome::draw(Termination::Adjudication));
+        }
+        if let Some(max) = self.config.max_moves
+            && self.plies >= 2 * max
+        {
+            return Some(Outcome::draw(Termination::MoveLimit));
+        }
+        None
+    }
+
+    fn count_resign(&mut self, white_score: Option<i32>) {
+        let Some(rule) = self.config.resign else {
+            return;
+        };
+        self.resign_streak = match white_score {
+            Some(score) if score >= rule.score => self.resign_streak.max(0) + 1,
+            Some(score) if score <= -rule.score => self.resign_streak.min(0) - 1,
+            _ => 0,
+        };
+    }
+
+    fn count_draw(&mut self, white_score: Option<i32>, move_number: u16) {
+        let Some(rule) = self.config.draw else {
+            return;
+        };
+        self.draw_streak = match white_score {
+            Some(score) if move_number >= rule.after_move && score.abs() <= rule.score => {
+                self.draw_streak + 1
+            }
+            _ => 0,
+        };
+    }
+}
+
+#[cfg(test)]
+mod adjudicate_test {
+    use arena::board::square;
+
+    use super::*;
+
+    // Knights hopping out and back, forever
+    fn shuffle(ply: usize) -> Move {
+        let moves = [
+            Move::quiet(square("g1"), square("f3")),
+            Move::quiet(square("g8"), square("f6")),
+            Move::quiet(square("f3"), square("g1")),
+            Move::quiet(square("f6"), square("g8")),
+        ];
+        moves[ply % 4]
+    }
+
+    // Runs the scores through, returning the ply the game was called on
+    fn run(config: AdjudicationConfig, scores: &[Option<i32>]) -> Option<(usize, Outcome)> {
+        let mut adjudicator = Adjudicator::new(config, &Game::new());
+        scores
+            .iter()
+            .enumerate()
+            .find_map(|(ply, &score)| Some(ply).zip(adjudicator.report(shuffle(ply), score)))
+    }
+
+    #[test]
+    fn test_resign() {
+        let config = AdjudicationConfig {
+            resign: Some(ResignRule {
+                score: 500,
+                moves: 2,
+            }),
+            ..Default::default()
+        };
+        // Black's engine sees itself lost as much as white's sees the win
+        let agreed = [Some(600), Some(-700), Some(550), Some(-900)];
+        let outcome = Outcome::win(Color::White, Termination::Adjudication);
+        assert_eq!(run(config.clone(), &agreed), Some((3, outcome)));
+
+        // One engine disagreeing for a move starts the count again
+        let disputed = [
+            Some(600),
+            Some(-700),
+            Some(550),
+            Some(100),
+            Some(600),
+            Some(-700),
+        ];
+        assert_eq!(run(config.clone(), &disputed), None);
+
+        let black_wins = [Some(-800), Some(800), Some(-800), Some(800)];
+        let outcome = Outcome::win(Color::Black, Termination::Adjudication);
+        assert_eq!(run(config.clone(), &black_wins), Some((3, outcome)));
+
+        let unscored = [Some(600), Some(-700), None, Some(-900), Some(600)];
+        assert_eq!(run(config, &unscored), None);
+    }
+
+    #[test]
+    fn test_draw() {
+        let config = AdjudicationConfig {
+            draw: Some(DrawRule {
+                score: 10,
+                moves: 2,
+                after_move: 3,
+            }),
+            ..Default::default()
+        };
+        // Quiet from the start, but the count only opens at move 3
+        let level = [Some(0); 8];
+        let outcome = Outcome::draw(Termination::Adjudication);
+        assert_eq!(run(config.clone(), &level), Some((7, outcome)));
+
+        let mut swing = [Some(5); 11];
+        swing[6] = Some(-40);
+        assert_eq!(run(config, &swing), Some((10, outcome)));
+    }
+
+    #[test]
+    fn test_zero_move_rules() {
+        // A rule of zero moves still waits for a score from each engine
+        let config = AdjudicationConfig {
+            resign: Some(ResignRule {
+                score: 500,
+                moves: 0,
+            }),
+            draw: Some(DrawRule {
+                score: 10,
+                moves: 0,
+                after_move: 1,
+            }),
+            ..Default::default()
+        };
+        let outcome = Outcome::win(Color::White, Termination::Adjudication);
+        assert_eq!(
+            run(config.clone(), &[Some(600), Some(-600)]),
+            Some((1, outcome))
+        );
+        let outcome = Outcome::draw(Termination::Adjudication);
+        assert_eq!(run(config, &[Some(0), Some(0)]), Some((1, outcome)));
+    }
+
+    #[test]
+    fn test_max_moves() {
+        let config = AdjudicationConfig {
+            max_moves: Some(3),
+            ..Default::default()
+        };
+        let outcome = Outcome::draw(Termination::MoveLimit);
+        assert_eq!(run(config.clone(), &[None; 10]), Some((5, outcome)));
+        assert_eq!(outcome.pgn_termination(), "adjudication");
+
+        let mut adjudicator = Adjudicator::new(config, &Game::new());
+        adjudicator.report(shuffle(0), None);
+        assert_eq!(adjudicator.game().side(), Color::Black);
+    }
+}
@@ -0,0 +1,555 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use arena::piece::Color;
+
+// Where the clock reads the time from. Only differences between readings
+// matter, so any fixed origin will do.
+pub trait TimeSource {
+    fn now(&self) -> Duration;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicTime {
+    origin: Instant,
+}
+
+impl MonotonicTime {
+    pub fn new() -> Self {
+        MonotonicTime {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for MonotonicTime {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+// Time that only moves when told to. Clones share the same reading, so a test
+// can keep one and hand the other to the clock.
+#[derive(Debug, Clone, Default)]
+pub struct ManualTime {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos
+            .fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+// One stage of a classical control. 40/90+30 is 40 moves in 90 minutes then
+// 30 minutes for the rest, with 30 seconds added per move throughout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    // None for the rest of the game. A last period with a move count repeats.
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub increment: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeControl {
+    SuddenDeath {
+        base: Duration,
+    },
+    // The increment is added after every move
+    Fischer {
+        base: Duration,
+        increment: Duration,
+    },
+    // Time used on a move is given back, up to the delay
+    Bronstein {
+        base: Duration,
+        delay: Duration,
+    },
+    // The clock only starts counting once the delay has passed
+    SimpleDelay {
+        base: Duration,
+        delay: Duration,
+    },
+    Periods(Vec<Period>),
+    // After the main time, every move must be made within a period. Going over
+    // uses the period up, and the flag falls once none are left.
+    ByoYomi {
+        base: Duration,
+        period: Duration,
+        periods: u32,
+    },
+}
+
+// What one side has to work with, in the shape UCI's go command wants it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLeft {
+    pub remaining: Duration,
+    // Time the side can count on getting per move on top of `remaining`
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SideClock {
+    remaining: Duration,
+    period: usize,
+    moves_in_period: u32,
+    byo_yomi_periods: u32,
+}
+
+// A chess clock for both sides. Only the side to move's time runs; pressing the
+// clock ends their move and starts the opponent's.
+pub struct Clock<T: TimeSource = MonotonicTime> {
+    control: TimeControl,
+    source: T,
+    sides: [SideClock; 2],
+    turn: Color,
+    running: bool,
+    paused: bool,
+    // When the current stretch of the turn started, and the time used in the
+    // turn before it, e.g. before a pause
+    turn_started: Duration,
+    turn_used: Duration,
+    flagged: Option<Color>,
+}
+
+impl Clock<MonotonicTime> {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_source(control, MonotonicTime::new())
+    }
+}
+
+impl<T: TimeSource> Clock<T> {
+    pub fn with_source(control: TimeControl, source: T) -> Self {
+        let side = match &control {
+            TimeControl::SuddenDeath { base }
+            | TimeControl::Fischer { base, .. }
+            | TimeControl::Bronstein { base, .. }
+            | TimeControl::SimpleDelay { base, .. } => SideClock {
+                remaining: *base,
+                period: 0,
+                moves_in_period: 0,
+                byo_yomi_periods: 0,
+            },
+            TimeControl::Periods(periods) => SideClock {
+                remaining: periods.first().map_or(Duration::ZERO, |p| p.time),
+                period: 0,
+                moves_in_period: 0,
+                byo_yomi_periods: 0,
+            },
+            TimeControl::ByoYomi { base, periods, .. } => SideClock {
+                remaining: *base,
+                period: 0,
+                moves_in_period: 0,
+                byo_yomi_periods: *periods,
+            },
+        };
+        Clock {
+            control,
+            source,
+            sides: [side; 2],
+            turn: Color::White,
+            running: false,
+            paused: false,
+            turn_started: Duration::ZERO,
+            turn_used: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    // Whose time is running, or would be if the clock were going
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running && !self.paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Sets `side`'s time running from a fresh turn
+    pub fn start(&mut self, side: Color) {
+        if self.flagged.is_some() {
+            return;
+        }
+        self.turn = side;
+        self.running = true;
+        self.paused = false;
+        self.turn_started = self.source.now();
+        self.turn_used = Duration::ZERO;
+    }
+
+    // Stops the clock for good, e.g. when the game ends on the board. The time
+    // used so far stays on the side to move's clock.
+    pub fn stop(&mut self) {
+        self.turn_used = self.turn_elapsed();
+        self.running = false;
+        self.paused = false;
+    }
+
+    pub fn pause(&mut self) {
+        if self.is_running() {
+            self.turn_used = self.turn_elapsed();
+            self.paused = true;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running && self.paused {
+            self.turn_started = self.source.now();
+            self.paused = false;
+        }
+    }
+
+    // Ends the side to move's turn: their time is charged, the control's
+    // increment or delay applied, and the opponent's time starts. A move made
+    // after the flag fell, or while the clock is paused, doesn't count.
+    pub fn press(&mut self) {
+        if !self.running || self.paused || self.flagged.is_some() {
+            return;
+        }
+        let mover = self.turn;
+        let elapsed = self.turn_elapsed();
+        if elapsed > self.allowance(mover) {
+            self.flagged = Some(mover);
+            self.turn_used = elapsed;
+            self.running = false;
+            return;
+        }
+        self.charge(mover, elapsed);
+        self.turn = mover.opposite();
+        self.turn_started = self.source.now();
+        self.turn_used = Duration::ZERO;
+    }
+
+    // The side whose time ran out, if any
+    pub fn flagged(&self) -> Option<Color> {
+        if self.flagged.is_some() {
+            return self.flagged;
+        }
+        (self.running && self.turn_elapsed() > self.allowance(self.turn)).then_some(self.turn)
+    }
+
+    // Main time left on `side`'s clock as it would show now. A running delay
+    // isn't taken off, and byo-yomi periods aren't included.
+    pub fn remaining(&self, side: Color) -> Duration {
+        let clock = &self.sides[side as usize];
+        if side != self.turn {
+            return clock.remaining;
+        }
+        let elapsed = self.turn_elapsed();
+        match &self.control {
+            TimeControl::SimpleDelay { delay, .. } => clock
+                .remaining
+                .saturating_sub(elapsed.saturating_sub(*delay)),
+            _ => clock.remaining.saturating_sub(elapsed),
+        }
+    }
+
+    pub fn byo_yomi_periods(&self, side: Color) -> u32 {
+        self.sides[side as usize].byo_yomi_periods
+    }
+
+    pub fn time_left(&self, side: Color) -> TimeLeft {
+        let clock = &self.sides[side as usize];
+        let (increment, moves_to_go) = match &self.control {
+            TimeControl::SuddenDeath { .. } => (Duration::ZERO, None),
+            TimeControl::Fischer { increment, .. } => (*increment, None),
+            TimeControl::Bronstein { delay, .. } | TimeControl::SimpleDelay { delay, .. } => {
+                (*delay, None)
+            }
+            TimeControl::Periods(periods) => match periods.get(clock.period) {
+                Some(period) => (
+                    period.increment,
+                    period.moves.map(|moves| moves - clock.moves_in_period),
+                ),
+                None => (Duration::ZERO, None),
+            },
+            TimeControl::ByoYomi { period, .. } if clock.byo_yomi_periods > 0 => (*period, None),
+            TimeControl::ByoYomi { .. } => (Duration::ZERO, None),
+        };
+        TimeLeft {
+            remaining: self.remaining(side),
+            increment,
+            moves_to_go,
+        }
+    }
+
+    fn turn_elapsed(&self) -> Duration {
+        if self.is_running() {
+            self.turn_used + self.source.now().saturating_sub(self.turn_started)
+        } else {
+            self.turn_used
+        }
+    }
+
+    // The longest a turn of `side` can last before the flag falls
+    fn allowance(&self, side: Color) -> Duration {
+        let clock = &self.sides[side as usize];
+        match &self.control {
+            TimeControl::SimpleDelay { delay, .. } => clock.remaining + *delay,
+            TimeControl::ByoYomi { period, .. } => {
+                clock.remaining + *period * clock.byo_yomi_periods
+            }
+            _ => clock.remaining,
+        }
+    }
+
+    // Books a finished move that stayed within the allowance
+    fn charge(&mut self, side: Color, elapsed: Duration) {
+        let clock = &mut self.sides[side as usize];
+        match &self.control {
+            TimeControl::SuddenDeath { .. } => clock.remaining -= elapsed,
+            TimeControl::Fischer { increment, .. } => {
+                clock.remaining = clock.remaining - elapsed + *increment;
+            }
+            TimeControl::Bronstein { delay, .. } => {
+                clock.remaining = clock.remaining - elapsed + elapsed.min(*delay);
+            }
+            TimeControl::SimpleDelay { delay, .. } => {
+                clock.remaining -= elapsed.saturating_sub(*delay);
+            }
+            TimeControl::Periods(periods) => {
+                let Some(period) = periods.get(clock.period) else {
+                    return;
+                };
+                clock.remaining = clock.remaining - elapsed + period.increment;
+                clock.moves_in_period += 1;
+                if Some(clock.moves_in_period) == period.moves {
+                    clock.moves_in_period = 0;
+                    if clock.period + 1 < periods.len() {
+                        clock.period += 1;
+                    }
+                    clock.remaining += periods[clock.period].time;
+                }
+            }
+            TimeControl::ByoYomi { period, .. } => {
+                if elapsed <= clock.remaining {
+                    clock.remaining -= elapsed;
+                } else {
+                    // A move inside the period keeps it, each period gone past is lost
+                    let over = elapsed - clock.remaining;
+                    let lost = over.as_nanos().div_ceil(period.as_nanos()) - 1;
+                    clock.remaining = Duration::ZERO;
+                    clock.byo_yomi_periods -= lost as u32;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod clock_test {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn started(control: TimeControl) -> (Clock<ManualTime>, ManualTime) {
+        let time = ManualTime::new();
+        let mut clock = Clock::with_source(control, time.clone());
+        clock.start(Color::White);
+        (clock, time)
+    }
+
+    // Each side takes the given seconds for its move, white first
+    fn play(clock: &mut Clock<ManualTime>, time: &ManualTime, moves: &[u64]) {
+        for &s in moves {
+            time.advance(secs(s));
+            clock.press();
+        }
+    }
+
+    #[test]
+    fn test_sudden_death_and_flag() {
+        let (mut clock, time) = started(TimeControl::SuddenDeath { base: secs(60) });
+        play(&mut clock, &time, &[10, 20]);
+        assert_eq!(clock.remaining(Color::White), secs(50));
+        assert_eq!(clock.remaining(Color::Black), secs(40));
+        assert_eq!(clock.turn(), Color::White);
+
+        time.advance(secs(50));
+        assert_eq!(clock.flagged(), None);
+        time.advance(secs(1));
+        assert_eq!(clock.flagged(), Some(Color::White));
+        assert_eq!(clock.remaining(Color::White), Duration::ZERO);
+        // Too late, the move doesn't count
+        clock.press();
+        assert_eq!(clock.flagged(), Some(Color::White));
+        assert_eq!(clock.turn(), Color::White);
+        assert!(!clock.is_running());
+    }
+
+    #[test]
+    fn test_fischer_and_bronstein() {
+        let (mut clock, time) = started(TimeControl::Fischer {
+            base: secs(60),
+            increment: secs(5),
+        });
+        play(&mut clock, &time, &[10, 2]);
+        assert_eq!(clock.remaining(Color::White), secs(55));
+        assert_eq!(clock.remaining(Color::Black), secs(63));
+
+        // Bronstein gives back at most the delay, so the clock never gains
+        let (mut clock, time) = started(TimeControl::Bronstein {
+            base: secs(60),
+            delay: secs(5),
+        });
+        play(&mut clock, &time, &[10, 2]);
+        assert_eq!(clock.remaining(Color::White), secs(55));
+        assert_eq!(clock.remaining(Color::Black), secs(60));
+    }
+
+    #[test]
+    fn test_simple_delay() {
+        let (mut clock, time) = started(TimeControl::SimpleDelay {
+            base: secs(10),
+            delay: secs(5),
+        });
+        time.advance(secs(4));
+        assert_eq!(clock.remaining(Color::White), secs(10));
+        time.advance(secs(3));
+        assert_eq!(clock.remaining(Color::White), secs(8));
+        clock.press();
+        assert_eq!(clock.remaining(Color::White), secs(8));
+
+        // Ten seconds plus the delay is all black gets
+        time.advance(secs(15));
+        assert_eq!(clock.flagged(), None);
+        time.advance(Duration::from_millis(1));
+        assert_eq!(clock.flagged(), Some(Color::Black));
+    }
+
+    #[test]
+    fn test_periods() {
+        let minutes = |m: u64| secs(m * 60);
+        // 2/90 then 30 minutes for the rest, 30 seconds a move throughout
+        let (mut clock, time) = started(TimeControl::Periods(vec![
+            Period {
+                moves: Some(2),
+                time: minutes(90),
+                increment: secs(30),
+            },
+            Period {
+                moves: None,
+                time: minutes(30),
+                increment: secs(30),
+            },
+        ]));
+        let white = clock.time_left(Color::White);
+        assert_eq!(white.moves_to_go, Some(2));
+        assert_eq!(white.increment, secs(30));
+
+        play(&mut clock, &time, &[600, 60]);
+        assert_eq!(clock.time_left(Color::White).moves_to_go, Some(1));
+        assert_eq!(clock.remaining(Color::White), minutes(80) + secs(30));
+        play(&mut clock, &time, &[600, 60]);
+        // The second control's time arrives after the second move
+        assert_eq!(clock.remaining(Color::White), minutes(100) + secs(60));
+        assert_eq!(clock.remaining(Color::Black), minutes(118) + secs(60));
+        assert_eq!(clock.time_left(Color::White).moves_to_go, None);
+
+        // A repeating last period keeps adding time
+        let (mut clock, time) = started(TimeControl::Periods(vec![Period {
+            moves: Some(1),
+            time: secs(60),
+            increment: Duration::ZERO,
+        }]));
+        play(&mut clock, &time, &[10, 10, 10]);
+        assert_eq!(clock.remaining(Color::White), secs(160));
+        assert_eq!(clock.time_left(Color::White).moves_to_go, Some(1));
+    }
+
+    #[test]
+    fn test_byo_yomi() {
+        let (mut clock, time) = started(TimeControl::ByoYomi {
+            base: secs(60),
+            period: secs(30),
+            periods: 3,
+        });
+        // White runs out of main time 20 seconds into the first period
+        play(&mut clock, &time, &[80, 1]);
+        assert_eq!(clock.remaining(Color::White), Duration::ZERO);
+        assert_eq!(clock.byo_yomi_periods(Color::White), 3);
+        // 40 seconds uses up one period
+        play(&mut clock, &time, &[40, 1]);
+        assert_eq!(clock.byo_yomi_periods(Color::White), 2);
+        assert_eq!(clock.time_left(Color::White).increment, secs(30));
+
+        time.advance(secs(60));
+        assert_eq!(clock.flagged(), None);
+        time.advance(secs(1));
+        assert_eq!(clock.flagged(), Some(Color::White));
+    }
+
+    #[test]
+    fn test_pause_and_stop() {
+        let (mut clock, time) = started(TimeControl::SuddenDeath { base: secs(60) });
+        time.advance(secs(10));
+        clock.pause();
+        assert!(clock.is_paused());
+        time.advance(secs(100));
+        assert_eq!(clock.remaining(Color::White), secs(50));
+        assert_eq!(clock.flagged(), None);
+        clock.resume();
+        time.advance(secs(5));
+        clock.press();
+        assert_eq!(clock.remaining(Color::White), secs(45));
+
+        time.advance(secs(7));
+        clock.stop();
+        time.advance(secs(100));
+        assert!(!clock.is_running());
+        assert_eq!(clock.remaining(Color::Black), secs(53));
+        assert_eq!(clock.flagged(), None);
+    }
+
+    #[test]
+    fn test_press_while_paused() {
+        let (mut clock, time) = started(TimeControl::SuddenDeath { base: secs(60) });
+        time.advance(secs(10));
+        clock.pause();
+        clock.press();
+        assert_eq!(clock.turn(), Color::White);
+        time.advance(secs(100));
+        assert_eq!(clock.remaining(Color::White), secs(50));
+        assert_eq!(clock.remaining(Color::Black), secs(60));
+
+        clock.resume();
+        time.advance(secs(5));
+        clock.press();
+        assert_eq!(clock.turn(), Color::Black);
+        assert_eq!(clock.remaining(Color::White), secs(45));
+    }
+}
@@ -0,0 +1,193 @@
+use arena::{
+    attacks::pawn_attacks,
+    board::{Board, LIGHT_SQUARES},
+    game::Game,
+    piece::{Color, PieceType},
+};
+
+use crate::outcome::has_insufficient_material;
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+// Whether no series of legal moves can ever end in mate. Beyond bare material
+// this spots pawn walls that will never open: every pawn stuck behind another,
+// no pawn able to capture, no king able to get at an undefended enemy pawn, and
+// at most bishops of one square color that can't win or lose a pawn either.
+// It errs towards "not dead"; anything it can't rule out counts as playable.
+pub fn is_dead_position(game: &Game) -> bool {
+    if has_insufficient_material(game) {
+        return true;
+    }
+    let both = |pt| game.pieces(pt, Color::White) | game.pieces(pt, Color::Black);
+    if both(PieceType::Knight) | both(PieceType::Rook) | both(PieceType::Queen) != 0 {
+        return false;
+    }
+    if game.is_in_check() || game.en_passant.is_some() {
+        return false;
+    }
+    let bishops = both(PieceType::Bishop);
+    if bishops & LIGHT_SQUARES != 0 && bishops & !LIGHT_SQUARES != 0 {
+        return false;
+    }
+    // The color of square the bishops live on, if there are any
+    let bishop_squares = if bishops & LIGHT_SQUARES != 0 {
+        LIGHT_SQUARES
+    } else {
+        !LIGHT_SQUARES
+    };
+
+    let pawns = both(PieceType::Pawn);
+    let white_pawns = game.pieces(PieceType::Pawn, Color::White);
+    let black_pawns = game.pieces(PieceType::Pawn, Color::Black);
+    // Every pawn must have a pawn right in front of it, so none ever moves
+    if (white_pawns << 8) & !pawns != 0 || (black_pawns >> 8) & !pawns != 0 {
+        return false;
+    }
+
+    let covered = [
+        pawn_cover(white_pawns, Color::White),
+        pawn_cover(black_pawns, Color::Black),
+    ];
+    let regions = [Color::White, Color::Black].map(|color| {
+        let them = color.opposite();
+        game.king_square(color)
+            .map_or(0, |king| king_region(king, pawns | covered[them as usize]))
+    });
+
+    for color in [Color::White, Color::Black] {
+        let us = color as usize;
+        let them = color.opposite() as usize;
+        let their_pawns = game.pieces(PieceType::Pawn, color.opposite());
+        // A pawn capture would open the position
+        if covered[us] & their_pawns != 0 {
+            return false;
+        }
+        // Bishops could take pawns on their own color, and pawns only ever
+        // cover squares of their own color, so those must all be on the other
+        if game.pieces(PieceType::Bishop, color) != 0 && their_pawns & bishop_squares != 0 {
+            return false;
+        }
+        // The king could eat its way in through an undefended pawn
+        let reachable = king_spread(regions[us]);
+        if reachable & their_pawns & !covered[them] != 0 {
+            return false;
+        }
+        // Enemy bishops give check on their squares only. The king must always
+        // have a neighbor of the other color to step to that the enemy king
+        // can't cover.
+        if game.pieces(PieceType::Bishop, color.opposite()) != 0 {
+            let enemy_king_reach = king_spread(regions[them]);
+            let in_region =
+                |square: Option<u8>| square.is_some_and(|sq| regions[us] & (1 << sq) != 0);
+            let has_escape = |square: u8| {
+                let neighbors = orthogonal_neighbors(square);
+                let [north, south, east, west] = neighbors;
+                // The enemy king can't cover both of two opposite neighbors
+                // without standing next to ours
+                neighbors
+                    .into_iter()
+                    .flatten()
+                    .any(|n| regions[us] & !enemy_king_reach & (1 << n) != 0)
+                    || (in_region(north) && in_region(south))
+                    || (in_region(east) && in_region(west))
+            };
+            if !Board::new(regions[us] & bishop_squares)
+                .squares()
+                .all(has_escape)
+            {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Squares the given pawns attack
+fn pawn_cover(pawns: u64, color: Color) -> u64 {
+    Board::new(pawns)
+        .squares()
+        .fold(0, |cover, sq| cover | pawn_attacks(color, sq))
+}
+
+fn king_spread(squares: u64) -> u64 {
+    let row = squares | ((squares << 1) & !FILE_A) | ((squares >> 1) & !FILE_H);
+    row | (row << 8) | (row >> 8)
+}
+
+// Every square the king can ever walk to, never stepping on a pawn or a square
+// an enemy pawn covers. Pieces other than pawns move out of the way, so they
+// don't count.
+fn king_region(king: u8, blocked: u64) -> u64 {
+    let mut region = 1 << king;
+    loop {
+        let grown = region | (king_spread(region) & !blocked);
+        if grown == region {
+            return region;
+        }
+        region = grown;
+    }
+}
+
+// North, south, east and west, None off the board
+fn orthogonal_neighbors(square: u8) -> [Option<u8>; 4] {
+    let (file, rank) = (square % 8, square / 8);
+    [
+        (rank < 7).then(|| square + 8),
+        (rank > 0).then(|| square - 8),
+        (file < 7).then(|| square + 1),
+        (file > 0).then(|| square - 1),
+    ]
+}
+
+#[cfg(test)]
+mod dead_test {
+    use super::*;
+
+    fn dead(fen: &str) -> bool {
+        is_dead_position(&Game::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_locked_pawns() {
+        // A closed wall across the board with only kings behind it
+        assert!(dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/8 w - - 0 1"));
+        assert!(dead("8/4k3/8/1p1p1p1p/pP1P1P1P/P7/8/2K5 b - - 0 1"));
+        // Pawns stacked behind a blocked one are stuck too
+        assert!(dead("8/8/1k6/p1p1p1p1/P1P1P1P1/6P1/3K4/8 w - - 0 1"));
+
+        // An open file lets the white king round to take e5
+        assert!(!dead("8/8/1k6/p1p1p3/P1P1P3/8/3K4/8 w - - 0 1"));
+        // A pawn capture is on
+        assert!(!dead("8/8/1k6/p1p1p1pp/P1P1P1PP/8/3K4/8 w - - 0 1"));
+        // A pawn free to advance
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K3P/8 w - - 0 1"));
+        // Any other piece could break through
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/7N w - - 0 1"));
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/7R w - - 0 1"));
+        assert!(!dead("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        assert!(!dead(arena::fen::START_FEN));
+    }
+
+    #[test]
+    fn test_bishops_behind_the_wall() {
+        // The black pawns stand on dark squares, out of reach of light bishops
+        assert!(dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/5B2 w - - 0 1"));
+        assert!(dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/3B1B2 w - - 0 1"));
+        // A dark bishop can take the black pawns
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/2B5 w - - 0 1"));
+        // Nor can a black one on the dark squares touch the light white pawns
+        assert!(dead("8/8/1k6/p1p1p1p1/P1P1P1P1/b7/3K4/8 w - - 0 1"));
+        // but a light one can
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/1b6 w - - 0 1"));
+        // Bishops on both colors
+        assert!(!dead("8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4/2b2B2 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_material_only() {
+        assert!(dead("8/8/4k3/8/8/3KN3/8/8 w - - 0 1"));
+        assert!(dead("8/8/4k3/2b5/8/3KB3/8/B7 w - - 0 1"));
+        assert!(!dead("8/8/4k3/3b4/8/3KB3/8/8 w - - 0 1"));
+    }
+}
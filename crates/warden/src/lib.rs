@@ -1,14 +1,17 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod adjudicate;
+pub mod clock;
+pub mod dead;
+pub mod outcome;
+pub mod session;
+pub mod validate;
+pub mod variant;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use adjudicate::{AdjudicationConfig, Adjudicator, DrawRule, ResignRule};
+pub use clock::{Clock, ManualTime, MonotonicTime, Period, TimeControl, TimeLeft, TimeSource};
+pub use dead::is_dead_position;
+pub use outcome::{
+    Outcome, Termination, claimable_draw, has_insufficient_material, has_mating_material, outcome,
+};
+pub use session::{GameRecord, Session, SessionError, SessionState};
+pub use validate::{IllegalMove, validate_move, validate_uci};
+pub use variant::{Antichess, KingOfTheHill, RuleSet, Standard, ThreeCheck, rules};
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use arena::{
+    board::LIGHT_SQUARES,
+    game::Game,
+    piece::{Color, PieceType},
+};
+
+use crate::{dead::is_dead_position, variant::rules};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    // No mate is possible for other reasons, e.g. a locked pawn wall
+    DeadPosition,
+    // Claimable by either player
+    FiftyMoves,
+    ThreefoldRepetition,
+    // Ends the game on the board, no claim needed
+    SeventyFiveMoves,
+    FivefoldRepetition,
+    // Decided off the board
+    Resignation,
+    Agreement,
+    // A draw when the opponent couldn't have mated anyway
+    Timeout,
+    // Called by the match runner from the engines' scores or the game length
+    Adjudication,
+    MoveLimit,
+    // Variant wins: a king on the center, the third check, and in antichess
+    // giving away every piece
+    KingOfTheHill,
+    ThreeChecks,
+    AllPiecesLost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    // None for a draw
+    pub winner: Option<Color>,
+    pub termination: Termination,
+}
+
+impl Outcome {
+    pub fn draw(termination: Termination) -> Self {
+        Outcome {
+            winner: None,
+            termination,
+        }
+    }
+
+    pub fn win(winner: Color, termination: Termination) -> Self {
+        Outcome {
+            winner: Some(winner),
+            termination,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.winner.is_none()
+    }
+
+    // The result as written in PGN
+    pub fn result(&self) -> &'static str {
+        match self.winner {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+
+    // Value for the PGN Termination tag
+    pub fn pgn_termination(&self) -> &'static str {
+        match self.termination {
+            Termination::Timeout => "time forfeit",
+            Termination::Adjudication | Termination::MoveLimit => "adjudication",
+            _ => "normal",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.result(), self.termination)
+    }
+}
+
+// How the game stands after the moves played so far, None while it goes on,
+// by the rules of the game's variant. Only endings that need no claim count
+// here, see claimable_draw for the rest. Repetitions are counted over the
+// moves played on `game` itself.
+pub fn outcome(game: &Game) -> Option<Outcome> {
+    rules(game.variant).outcome(game)
+}
+
+// A draw the side to move may claim right now: fifty moves without a capture
+// or pawn move, or the third occurrence of the position
+pub fn claimable_draw(game: &Game) -> Option<Termination> {
+    if game.halfmove_clock >= 100 {
+        Some(Termination::FiftyMoves)
+    } else if game.repetitions() >= 2 {
+        Some(Termination::ThreefoldRepetition)
+    } else {
+        None
+    }
+}
+
+// Neither side can ever mate: bare kings, a lone minor piece, or bishops that
+// all stand on squares of one color
+pub fn has_insufficient_material(game: &Game) -> bool {
+    let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen];
+    if [Color::White, Color::Black]
+        .into_iter()
+        .any(|color| heavy.iter().any(|&pt| game.pieces(pt, color) != 0))
+    {
+        return false;
+    }
+    let knights =
+        game.pieces(PieceType::Knight, Color::White) | game.pieces(PieceType::Knight, Color::Black);
+    let bishops =
+        game.pieces(PieceType::Bishop, Color::White) | game.pieces(PieceType::Bishop, Color::Black);
+    if knights == 0 {
+        return bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0;
+    }
+    (knights | bishops).count_ones() == 1
+}
+
+// Whether `color` could still mate by some series of legal moves, as decides a
+// game lost on time. A lone king can't, nor a single minor piece or bishops of
+// one square color against a bare king. Against any other material a helpmate
+// is assumed possible unless the position is dead.
+pub fn has_mating_material(game: &Game, color: Color) -> bool {
+    if [PieceType::Pawn, PieceType::Rook, PieceType::Queen]
+        .into_iter()
+        .any(|pt| game.pieces(pt, color) != 0)
+    {
+        return true;
+    }
+    let knights = game.pieces(PieceType::Knight, color);
+    let bishops = game.pieces(PieceType::Bishop, color);
+    if knights | bishops == 0 {
+        return false;
+    }
+    let them = color.opposite();
+    if game.color_pieces(them) == game.pieces(PieceType::King, them) {
+        let both_bishop_colors = bishops & LIGHT_SQUARES != 0 && bishops & !LIGHT_SQUARES != 0;
+        return knights.count_ones() >= 2 || (knights != 0 && bishops != 0) || both_bishop_colors;
+    }
+    !is_dead_position(game)
+}
+
+#[cfg(test)]
+mod outcome_test {
+    use arena::{board::square, moves::Move};
+
+    use super::*;
+
+    fn outcome_of(fen: &str) -> Option<Outcome> {
+        outcome(&Game::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_mate_and_stalemate() {
+        assert_eq!(outcome(&Game::new()), None);
+        let mate = outcome_of("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(mate, Outcome::win(Color::White, Termination::Checkmate));
+        assert_eq!(mate.result(), "1-0");
+        assert_eq!(mate.to_string(), "1-0 (Checkmate)");
+
+        let stalemate = outcome_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(stalemate, Outcome::draw(Termination::Stalemate));
+        assert_eq!(stalemate.result(), "1/2-1/2");
+
+        // Mate delivered on the 150th half move still counts as mate
+        let late_mate = outcome_of("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 120").unwrap();
+        assert_eq!(late_mate.termination, Termination::Checkmate);
+    }
+
+    #[test]
+    fn test_move_rules() {
+        let fen = |clock: u16| format!("4k3/8/8/8/8/8/4P3/4K2R w - - {clock} 80");
+        assert_eq!(claimable_draw(&Game::from_fen(&fen(99)).unwrap()), None);
+        assert_eq!(
+            claimable_draw(&Game::from_fen(&fen(100)).unwrap()),
+            Some(Termination::FiftyMoves)
+        );
+        assert_eq!(outcome_of(&fen(149)), None);
+        assert_eq!(
+            outcome_of(&fen(150)),
+            Some(Outcome::draw(Termination::SeventyFiveMoves))
+        );
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut game = Game::new();
+        let shuffle = [
+            Move::quiet(square("g1"), square("f3")),
+            Move::quiet(square("g8"), square("f6")),
+            Move::quiet(square("f3"), square("g1")),
+            Move::quiet(square("f6"), square("g8")),
+        ];
+        for mv in shuffle {
+            game.make_move(mv);
+        }
+        assert_eq!(claimable_draw(&game), None);
+        for mv in shuffle {
+            game.make_move(mv);
+        }
+        assert_eq!(
+            claimable_draw(&game),
+            Some(Termination::ThreefoldRepetition)
+        );
+        assert_eq!(outcome(&game), None);
+        for mv in shuffle.iter().chain(&shuffle) {
+            game.make_move(*mv);
+        }
+        assert_eq!(
+            outcome(&game),
+            Some(Outcome::draw(Termination::FivefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for (fen, insufficient) in [
+            ("8/8/4k3/8/8/3K4/8/8 w - - 0 1", true),
+            ("8/8/4k3/8/8/3KN3/8/8 w - - 0 1", true),
+            ("8/8/4k3/8/8/3KB3/8/8 b - - 0 1", true),
+            // Bishops on both sides, all on dark squares
+            ("8/8/4k3/2b5/8/3KB3/8/B7 w - - 0 1", true),
+            // Opposite-colored bishops can still mate in a corner
+            ("8/8/4k3/3b4/8/3KB3/8/8 w - - 0 1", false),
+            ("8/8/4k3/8/8/3KNN2/8/8 w - - 0 1", false),
+            ("8/8/4kn2/8/8/3KB3/8/8 w - - 0 1", false),
+            ("8/8/4k3/8/8/3K4/7P/8 w - - 0 1", false),
+            ("8/8/4k3/8/8/3K4/8/7R w - - 0 1", false),
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            assert_eq!(has_insufficient_material(&game), insufficient, "{fen}");
+        }
+        for (fen, white, black) in [
+            ("8/8/4k3/8/8/3KN3/8/8 w - - 0 1", false, false),
+            ("8/8/4k3/8/8/3KNN2/8/8 w - - 0 1", true, false),
+            ("8/8/4k3/8/8/3KBB2/8/8 w - - 0 1", true, false),
+            // A knight can mate a king boxed in by its own pawn
+            ("8/8/4k3/4p3/8/3KN3/8/8 w - - 0 1", true, true),
+            ("8/8/4k3/8/8/3K4/8/7R w - - 0 1", true, false),
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            assert_eq!(has_mating_material(&game, Color::White), white, "{fen}");
+            assert_eq!(has_mating_material(&game, Color::Black), black, "{fen}");
+        }
+        assert_eq!(
+            outcome_of("8/8/4k3/8/8/3KN3/8/8 w - - 0 1"),
+            Some(Outcome::draw(Termination::InsufficientMaterial))
+        );
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt;
+
+use arena::{game::Game, moves::Move, piece::Color, variant::Variant};
+
+use crate::{
+    clock::{Clock, MonotonicTime, TimeSource},
+    outcome::{Outcome, Termination, claimable_draw, outcome},
+    validate::{IllegalMove, validate_uci},
+    variant::rules,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Ongoing,
+    Finished(Outcome),
+    // Called off before it really started, no result
+    Aborted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    GameOver,
+    NotYourTurn,
+    Illegal(IllegalMove),
+    NoDrawOffer,
+    NothingToClaim,
+    TooLateToAbort,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::GameOver => write!(f, "the game is over"),
+            SessionError::NotYourTurn => write!(f, "it is not your turn"),
+            SessionError::Illegal(reason) => write!(f, "illegal move: {reason}"),
+            SessionError::NoDrawOffer => write!(f, "there is no draw offer to answer"),
+            SessionError::NothingToClaim => write!(f, "there is no draw to claim"),
+            SessionError::TooLateToAbort => write!(f, "the game can no longer be aborted"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+// Everything a PGN writer needs once the game is over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub variant: Variant,
+    // Only set when the game didn't begin from the variant's usual position
+    pub start_fen: Option<String>,
+    pub moves: Vec<Move>,
+    // "1-0", "0-1", "1/2-1/2", or "*" while unfinished or aborted
+    pub result: &'static str,
+    pub termination: Option<&'static str>,
+    pub outcome: Option<Outcome>,
+}
+
+// One game between two players, keeping the rules: whose turn it is, the
+// clock, draw offers, claims, resignations and aborts
+pub struct Session<T: TimeSource = MonotonicTime> {
+    game: Game,
+    start_fen: String,
+    moves: Vec<Move>,
+    clock: Option<Clock<T>>,
+    draw_offer: Option<Color>,
+    state: SessionState,
+}
+
+impl Session<MonotonicTime> {
+    pub fn new(game: Game) -> Self {
+        Self::build(game, None)
+    }
+}
+
+impl<T: TimeSource> Session<T> {
+    // The side to move's time starts running straight away
+    pub fn with_clock(game: Game, mut clock: Clock<T>) -> Self {
+        clock.start(game.side());
+        Self::build(game, Some(clock))
+    }
+
+    fn build(game: Game, clock: Option<Clock<T>>) -> Self {
+        let start_fen = game.to_fen();
+        let mut session = Session {
+            game,
+            start_fen,
+            moves: Vec::new(),
+            clock,
+            draw_offer: None,
+            state: SessionState::Ongoing,
+        };
+        // A position set up already finished
+        if let Some(outcome) = outcome(&session.game) {
+            session.finish(SessionState::Finished(outcome));
+        }
+        session
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn clock(&self) -> Option<&Clock<T>> {
+        self.clock.as_ref()
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    // Plays `side`'s move given in UCI notation. A move arriving after the flag
+    // fell ends the game on time instead.
+    pub fn play(&mut self, side: Color, uci: &str) -> Result<Move, SessionError> {
+        self.ensure_turn(side)?;
+        let mv = validate_uci(&self.game, uci).map_err(SessionError::Illegal)?;
+        if let Some(clock) = &mut self.clock {
+            clock.press();
+        }
+        if self.check_time() != SessionState::Ongoing {
+            return Err(SessionError::GameOver);
+        }
+
+        self.game.make_move(mv);
+        self.moves.push(mv);
+        // Playing on declines the opponent's offer, an offer made along with
+        // the move stands
+        if self.draw_offer == Some(side.opposite()) {
+            self.draw_offer = None;
+        }
+        if let Some(outcome) = outcome(&self.game) {
+            self.finish(SessionState::Finished(outcome));
+        }
+        Ok(mv)
+    }
+
+    // Looks at the clock, ending the game if a flag has fallen
+    pub fn check_time(&mut self) -> SessionState {
+        if self.state != SessionState::Ongoing {
+            return self.state;
+        }
+        let Some(flagged) = self.clock.as_ref().and_then(Clock::flagged) else {
+            return self.state;
+        };
+        let opponent = flagged.opposite();
+        let outcome = if rules(self.game.variant).can_win(&self.game, opponent) {
+            Outcome::win(opponent, Termination::Timeout)
+        } else {
+            Outcome::draw(Termination::Timeout)
+        };
+        self.finish(SessionState::Finished(outcome));
+        self.state
+    }
+
+    pub fn offer_draw(&mut self, side: Color) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        // Offering back is accepting
+        if self.draw_offer == Some(side.opposite()) {
+            return self.accept_draw(side);
+        }
+        self.draw_offer = Some(side);
+        Ok(())
+    }
+
+    pub fn accept_draw(&mut self, side: Color) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        if self.draw_offer != Some(side.opposite()) {
+            return Err(SessionError::NoDrawOffer);
+        }
+        self.finish(SessionState::Finished(Outcome::draw(
+            Termination::Agreement,
+        )));
+        Ok(())
+    }
+
+    pub fn decline_draw(&mut self, side: Color) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        if self.draw_offer != Some(side.opposite()) {
+            return Err(SessionError::NoDrawOffer);
+        }
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    // Threefold repetition or the fifty-move rule, claimed by the side to move
+    pub fn claim_draw(&mut self, side: Color) -> Result<Termination, SessionError> {
+        self.ensure_turn(side)?;
+        let termination = claimable_draw(&self.game).ok_or(SessionError::NothingToClaim)?;
+        self.finish(SessionState::Finished(Outcome::draw(termination)));
+        Ok(termination)
+    }
+
+    pub fn resign(&mut self, side: Color) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        self.finish(SessionState::Finished(Outcome::win(
+            side.opposite(),
+            Termination::Resignation,
+        )));
+        Ok(())
+    }
+
+    // Either player may call the game off until both have made a move
+    pub fn abort(&mut self) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        if self.moves.len() >= 2 {
+            return Err(SessionError::TooLateToAbort);
+        }
+        self.finish(SessionState::Aborted);
+        Ok(())
+    }
+
+    pub fn record(&self) -> GameRecord {
+        let outcome = match self.state {
+            SessionState::Finished(outcome) => Some(outcome),
+            _ => None,
+        };
+        let variant = self.game.variant;
+        let standard = self.start_fen == Game::new_variant(variant).to_fen();
+        GameRecord {
+            variant,
+            start_fen: (!standard).then(|| self.start_fen.clone()),
+            moves: self.moves.clone(),
+            result: outcome.map_or("*", |o| o.result()),
+            termination: match self.state {
+                SessionState::Ongoing => None,
+                SessionState::Finished(outcome) => Some(outcome.pgn_termination()),
+                SessionState::Aborted => Some("abandoned"),
+            },
+            outcome,
+        }
+    }
+
+    fn ensure_ongoing(&mut self) -> Result<(), SessionError> {
+        match self.check_time() {
+            SessionState::Ongoing => Ok(()),
+            _ => Err(SessionError::GameOver),
+        }
+    }
+
+    fn ensure_turn(&mut self, side: Color) -> Result<(), SessionError> {
+        self.ensure_ongoing()?;
+        if side != self.game.side() {
+            return Err(SessionError::NotYourTurn);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, state: SessionState) {
+        self.state = state;
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod session_test {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::clock::{ManualTime, TimeControl};
+
+    fn play_all(session: &mut Session<impl TimeSource>, moves: &[&str]) {
+        for uci in moves {
+            let side = session.game().side();
+            session.play(side, uci).unwrap();
+        }
+    }
+
+    fn timed(fen: &str, seconds: u64) -> (Session<ManualTime>, ManualTime) {
+        let time = ManualTime::new();
+        let clock = Clock::with_source(
+            TimeControl::SuddenDeath {
+                base: Duration::from_secs(seconds),
+            },
+            time.clone(),
+        );
+        (
+            Session::with_clock(Game::from_fen(fen).unwrap(), clock),
+            time,
+        )
+    }
+
+    #[test]
+    fn test_turns_and_mate() {
+        let mut session = Session::new(Game::new());
+        assert_eq!(
+            session.play(Color::Black, "e7e5"),
+            Err(SessionError::NotYourTurn)
+        );
+        assert!(matches!(
+            session.play(Color::White, "e2e5"),
+            Err(SessionError::Illegal(_))
+        ));
+        play_all(&mut session, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        let outcome = Outcome::win(Color::Black, Termination::Checkmate);
+        assert_eq!(session.state(), SessionState::Finished(outcome));
+        assert_eq!(
+            session.play(Color::White, "a2a3"),
+            Err(SessionError::GameOver)
+        );
+
+        let record = session.record();
+        assert_eq!(record.result, "0-1");
+        assert_eq!(record.termination, Some("normal"));
+        assert_eq!(record.start_fen, None);
+        assert_eq!(record.moves.len(), 4);
+        assert_eq!(record.moves[3].to_string(), "d8h4");
+    }
+
+    #[test]
+    fn test_draw_offers() {
+        let mut session = Session::new(Game::new());
+        assert_eq!(
+            session.accept_draw(Color::White),
+            Err(SessionError::NoDrawOffer)
+        );
+        session.offer_draw(Color::White).unwrap();
+        play_all(&mut session, &["e2e4"]);
+        // Still open after the offering side moved, gone once black plays on
+        assert_eq!(session.draw_offer(), Some(Color::White));
+        play_all(&mut session, &["e7e5"]);
+        assert_eq!(session.draw_offer(), None);
+
+        session.offer_draw(Color::Black).unwrap();
+        session.decline_draw(Color::White).unwrap();
+        session.offer_draw(Color::Black).unwrap();
+        assert_eq!(
+            session.accept_draw(Color::Black),
+            Err(SessionError::NoDrawOffer)
+        );
+        session.accept_draw(Color::White).unwrap();
+        assert_eq!(
+            session.state(),
+            SessionState::Finished(Outcome::draw(Termination::Agreement))
+        );
+        assert_eq!(session.record().result, "1/2-1/2");
+    }
+
+    #[test]
+    fn test_resign_and_claims() {
+        let mut session = Session::new(Game::new());
+        assert_eq!(
+            session.claim_draw(Color::White),
+            Err(SessionError::NothingToClaim)
+        );
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play_all(&mut session, &shuffle);
+        play_all(&mut session, &shuffle);
+        assert_eq!(
+            session.claim_draw(Color::Black),
+            Err(SessionError::NotYourTurn)
+        );
+        assert_eq!(
+            session.claim_draw(Color::White),
+            Ok(Termination::ThreefoldRepetition)
+        );
+
+        let fifty = Game::from_fen("4k3/8/8/8/8/8/4P3/4K2R w - - 100 80").unwrap();
+        let mut session = Session::new(fifty);
+        assert_eq!(
+            session.claim_draw(Color::White),
+            Ok(Termination::FiftyMoves)
+        );
+
+        let mut session = Session::new(Game::new());
+        play_all(&mut session, &["e2e4", "e7e5"]);
+        session.resign(Color::White).unwrap();
+        let outcome = Outcome::win(Color::Black, Termination::Resignation);
+        assert_eq!(session.state(), SessionState::Finished(outcome));
+        assert_eq!(session.resign(Color::Black), Err(SessionError::GameOver));
+    }
+
+    #[test]
+    fn test_abort() {
+        let mut session = Session::new(Game::new());
+        play_all(&mut session, &["e2e4"]);
+        session.abort().unwrap();
+        assert_eq!(session.state(), SessionState::Aborted);
+        let record = session.record();
+        assert_eq!(record.result, "*");
+        assert_eq!(record.termination, Some("abandoned"));
+
+        let mut session = Session::new(Game::new());
+        play_all(&mut session, &["e2e4", "e7e5"]);
+        assert_eq!(session.abort(), Err(SessionError::TooLateToAbort));
+        assert_eq!(session.record().result, "*");
+        assert_eq!(session.record().termination, None);
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let (mut session, time) = timed("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 60);
+        play_all(&mut session, &["e2e4", "e8d8"]);
+        time.advance(Duration::from_secs(61));
+        // Black only has a king, so white losing on time is a draw
+        assert_eq!(
+            session.play(Color::White, "e4e5"),
+            Err(SessionError::GameOver)
+        );
+        assert_eq!(
+            session.state(),
+            SessionState::Finished(Outcome::draw(Termination::Timeout))
+        );
+
+        let fen = "4k3/4p3/8/8/8/8/8/4K3 w - - 0 1";
+        let (mut session, time) = timed(fen, 60);
+        time.advance(Duration::from_secs(61));
+        let outcome = Outcome::win(Color::Black, Termination::Timeout);
+        assert_eq!(session.check_time(), SessionState::Finished(outcome));
+        let record = session.record();
+        assert_eq!(record.result, "0-1");
+        assert_eq!(record.termination, Some("time forfeit"));
+        assert_eq!(record.start_fen.as_deref(), Some(fen));
+    }
+
+    #[test]
+    fn test_variant_game() {
+        let mut session = Session::new(Game::new_variant(Variant::KingOfTheHill));
+        play_all(
+            &mut session,
+            &["e2e3", "a7a6", "e1e2", "a6a5", "e2d3", "a5a4"],
+        );
+        session.play(Color::White, "d3e4").unwrap();
+        assert_eq!(
+            session.state(),
+            SessionState::Finished(Outcome::win(Color::White, Termination::KingOfTheHill))
+        );
+        let record = session.record();
+        assert_eq!(record.variant, Variant::KingOfTheHill);
+        assert_eq!(record.start_fen, None);
+        assert_eq!(record.result, "1-0");
+    }
+}
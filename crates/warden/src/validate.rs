@@ -0,0 +1,500 @@
+use std::fmt;
+
+use arena::{
+    attacks::{between, king_attacks, knight_attacks, line, pawn_attacks},
+    board::{Board, parse_square, square_name},
+    game::Game,
+    moves::Move,
+    piece::{Color, Piece, PieceType},
+};
+use prophet::queries::find_move;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    // Not a move in from-square, to-square, promotion form
+    Unreadable,
+    NoPiece { square: u8 },
+    WrongColor { square: u8 },
+    OwnPieceOnTarget { square: u8 },
+    CannotMoveThatWay { piece: PieceType, from: u8, to: u8 },
+    PathBlocked { square: u8 },
+    LeavesKingInCheck,
+    CastlingInCheck,
+    CastlingThroughCheck { square: u8 },
+    CastlingRightLost { kingside: bool },
+    MissingPromotion,
+    InvalidPromotion { piece: PieceType },
+    // Antichess only, some capture is available
+    CaptureRequired,
+}
+
+fn piece_name(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "pawn",
+        PieceType::Knight => "knight",
+        PieceType::Bishop => "bishop",
+        PieceType::Rook => "rook",
+        PieceType::Queen => "queen",
+        PieceType::King => "king",
+    }
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            IllegalMove::Unreadable => write!(f, "not a move"),
+            IllegalMove::NoPiece { square } => {
+                write!(f, "there is no piece on {}", square_name(square))
+            }
+            IllegalMove::WrongColor { square } => {
+                write!(
+                    f,
+                    "the piece on {} belongs to the opponent",
+                    square_name(square)
+                )
+            }
+            IllegalMove::OwnPieceOnTarget { square } => {
+                write!(f, "{} is occupied by your own piece", square_name(square))
+            }
+            IllegalMove::CannotMoveThatWay { piece, from, to } => write!(
+                f,
+                "a {} cannot move from {} to {}",
+                piece_name(piece),
+                square_name(from),
+                square_name(to)
+            ),
+            IllegalMove::PathBlocked { square } => {
+                write!(
+                    f,
+                    "the way is blocked by the piece on {}",
+                    square_name(square)
+                )
+            }
+            IllegalMove::LeavesKingInCheck => write!(f, "the move leaves your king in check"),
+            IllegalMove::CastlingInCheck => write!(f, "you cannot castle out of check"),
+            IllegalMove::CastlingThroughCheck { square } => write!(
+                f,
+                "you cannot castle through or into check, {} is attacked",
+                square_name(square)
+            ),
+            IllegalMove::CastlingRightLost { kingside } => write!(
+                f,
+                "you can no longer castle {}",
+                if kingside { "kingside" } else { "queenside" }
+            ),
+            IllegalMove::MissingPromotion => {
+                write!(f, "a pawn reaching the last rank must promote")
+            }
+            IllegalMove::InvalidPromotion { piece } => {
+                write!(f, "this move cannot promote to a {}", piece_name(piece))
+            }
+            IllegalMove::CaptureRequired => write!(f, "a capture is available and must be made"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+// Checks a move given as it arrives from a player, squares and an optional
+// promotion piece, and returns it as a legal move or the first rule it breaks.
+// A king moved onto its own castling rook castles, as in Chess960 GUIs.
+pub fn validate_move(
+    game: &Game,
+    from: u8,
+    to: u8,
+    promotion: Option<PieceType>,
+) -> Result<Move, IllegalMove> {
+    if let Some(mv) = find_move(game, from, to, promotion) {
+        // find_move ignores the piece for moves that don't promote
+        return match promotion {
+            Some(piece) if !mv.is_promotion() => Err(IllegalMove::InvalidPromotion { piece }),
+            _ => Ok(mv),
+        };
+    }
+    Err(diagnose(game, from, to, promotion))
+}
+
+// Same as validate_move for a move in UCI notation such as "e7e8q"
+pub fn validate_uci(game: &Game, uci: &str) -> Result<Move, IllegalMove> {
+    let (Some(from), Some(to)) = (
+        uci.get(0..2).and_then(parse_square),
+        uci.get(2..4).and_then(parse_square),
+    ) else {
+        return Err(IllegalMove::Unreadable);
+    };
+    let promotion = match uci.get(4..) {
+        Some("") | None => None,
+        Some(piece) => {
+            let mut chars = piece.chars();
+            match (chars.next().and_then(Piece::from_char), chars.next()) {
+                (Some(piece), None) => Some(piece.piece_type()),
+                _ => return Err(IllegalMove::Unreadable),
+            }
+        }
+    };
+    validate_move(game, from, to, promotion)
+}
+
+// Works out why find_move had nothing for these squares, going through the
+// rules in the order a player would check them
+fn diagnose(game: &Game, from: u8, to: u8, promotion: Option<PieceType>) -> IllegalMove {
+    let us = game.side();
+    let Some(piece) = game.get_piece_at(from) else {
+        return IllegalMove::NoPiece { square: from };
+    };
+    if piece.color() != us {
+        return IllegalMove::WrongColor { square: from };
+    }
+    let piece_type = piece.piece_type();
+    let cannot = IllegalMove::CannotMoveThatWay {
+        piece: piece_type,
+        from,
+        to,
+    };
+    if from == to {
+        return cannot;
+    }
+    if piece_type == PieceType::King
+        && game.variant.has_royal_king()
+        && let Some(kingside) = castling_side(game, from, to)
+    {
+        return diagnose_castling(game, from, kingside).unwrap_or(cannot);
+    }
+    let target = game.get_piece_at(to);
+    if target.is_some_and(|p| p.color() == us) {
+        return IllegalMove::OwnPieceOnTarget { square: to };
+    }
+
+    let occupancy = game.all_pieces().bits();
+    let blocker = |squares: u64| {
+        let blocking = squares & occupancy;
+        (blocking != 0).then(|| {
+            // The one met first going from `from` towards `to`
+            let square = if to > from {
+                blocking.trailing_zeros()
+            } else {
+                63 - blocking.leading_zeros()
+            };
+            IllegalMove::PathBlocked {
+                square: square as u8,
+            }
+        })
+    };
+    let same_line = from % 8 == to % 8 || from / 8 == to / 8;
+    let reachable = match piece_type {
+        PieceType::Knight => knight_attacks(from) & (1 << to) != 0,
+        PieceType::King => king_attacks(from) & (1 << to) != 0,
+        PieceType::Rook => same_line,
+        PieceType::Bishop => line(from, to) != 0 && !same_line,
+        PieceType::Queen => line(from, to) != 0,
+        PieceType::Pawn => {
+            let (forward, start_rank) = match us {
+                Color::White => (8i16, 1),
+                Color::Black => (-8i16, 6),
+            };
+            let step = to as i16 - from as i16;
+            if pawn_attacks(us, from) & (1 << to) != 0 {
+                // Diagonally only onto a piece, or behind a pawn that just
+                // double pushed
+                target.is_some() || game.en_passant == Some(to)
+            } else if step == forward {
+                if target.is_some() {
+                    return IllegalMove::PathBlocked { square: to };
+                }
+                true
+            } else if step == 2 * forward && from / 8 == start_rank {
+                if let Some(blocked) = blocker(between(from, to) | (1 << to)) {
+                    return blocked;
+                }
+                true
+            } else {
+                false
+            }
+        }
+    };
+    if !reachable {
+        return cannot;
+    }
+    if matches!(
+        piece_type,
+        PieceType::Bishop | PieceType::Rook | PieceType::Queen
+    ) && let Some(blocked) = blocker(between(from, to))
+    {
+        return blocked;
+    }
+
+    if piece_type == PieceType::Pawn && (to / 8 == 0 || to / 8 == 7) {
+        match promotion {
+            None => return IllegalMove::MissingPromotion,
+            Some(PieceType::King) if !game.variant.has_royal_king() => {}
+            Some(piece @ (PieceType::Pawn | PieceType::King)) => {
+                return IllegalMove::InvalidPromotion { piece };
+            }
+            Some(_) => {}
+        }
+    }
+    // The piece can get there, so the only thing left is the king's safety, or
+    // in antichess a capture it passed over
+    if game.variant.has_royal_king() {
+        IllegalMove::LeavesKingInCheck
+    } else {
+        IllegalMove::CaptureRequired
+    }
+}
+
+// Whether a king move from `from` to `to` is an attempt to castle, and to which
+// side: two files along the back rank from the standard square, or onto the
+// king's own castling rook
+fn castling_side(game: &Game, from: u8, to: u8) -> Option<bool> {
+    let us = game.side();
+    let back_rank = match us {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    if from / 8 != back_rank || to / 8 != back_rank {
+        return None;
+    }
+    let onto_rook = [true, false].into_iter().find(|&kingside| {
+        game.castling_rook_square(us, kingside) == to
+            && game.pieces(PieceType::Rook, us) & (1 << to) != 0
+            && (game.chess960 || game.has_castling_right(us, kingside))
+    });
+    if onto_rook.is_some() {
+        return onto_rook;
+    }
+    let two_files = !game.chess960 && from % 8 == 4 && (to % 8 == 2 || to % 8 == 6);
+    two_files.then_some(to > from)
+}
+
+fn diagnose_castling(game: &Game, king: u8, kingside: bool) -> Option<IllegalMove> {
+    let us = game.side();
+    let rook = game.castling_rook_square(us, kingside);
+    if !game.has_castling_right(us, kingside) || game.pieces(PieceType::Rook, us) & (1 << rook) == 0
+    {
+        return Some(IllegalMove::CastlingRightLost { kingside });
+    }
+    if game.is_in_check() {
+        return Some(IllegalMove::CastlingInCheck);
+    }
+    let (king_to, rook_to) = Game::castling_destinations(us, kingside);
+    let king_path = between(king, king_to) | (1 << king_to);
+    let rook_path = between(rook, rook_to) | (1 << rook_to);
+    let others = game.all_pieces().bits() & !(1 << king) & !(1 << rook);
+    if let Some(square) = Board::new((king_path | rook_path) & others)
+        .squares()
+        .next()
+    {
+        return Some(IllegalMove::PathBlocked { square });
+    }
+    let them = game.color_pieces(us.opposite());
+    // Walk from the king outwards so the first attacked square is reported
+    let mut path: Vec<u8> = Board::new(king_path).squares().collect();
+    if king_to < king {
+        path.reverse();
+    }
+    path.into_iter()
+        .find(|&square| game.attackers_to(square, others).bits() & them != 0)
+        .map(|square| IllegalMove::CastlingThroughCheck { square })
+}
+
+#[cfg(test)]
+mod validate_test {
+    use arena::{board::square, variant::Variant};
+
+    use super::*;
+
+    fn check(fen: &str, uci: &str) -> Result<Move, IllegalMove> {
+        validate_uci(&Game::from_fen(fen).unwrap(), uci)
+    }
+
+    #[test]
+    fn test_legal_moves_pass() {
+        let game = Game::new();
+        assert_eq!(
+            validate_uci(&game, "g1f3"),
+            Ok(Move::quiet(square("g1"), square("f3")))
+        );
+        assert!(validate_uci(&game, "e2e4").unwrap().is_double_push());
+        let castle = check("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1h1").unwrap();
+        assert!(castle.is_castle());
+        let promotion = check("7k/P7/8/8/8/8/8/K7 w - - 0 1", "a7a8n").unwrap();
+        assert_eq!(promotion.promotion_piece(), Some(PieceType::Knight));
+    }
+
+    #[test]
+    fn test_piece_and_movement_reasons() {
+        let start = Game::new();
+        let reason = |uci: &str| validate_uci(&start, uci).unwrap_err();
+        assert_eq!(
+            reason("e4e5"),
+            IllegalMove::NoPiece {
+                square: square("e4")
+            }
+        );
+        assert_eq!(
+            reason("e7e5"),
+            IllegalMove::WrongColor {
+                square: square("e7")
+            }
+        );
+        assert_eq!(
+            reason("d1d2"),
+            IllegalMove::OwnPieceOnTarget {
+                square: square("d2")
+            }
+        );
+        assert_eq!(
+            reason("g1g3"),
+            IllegalMove::CannotMoveThatWay {
+                piece: PieceType::Knight,
+                from: square("g1"),
+                to: square("g3")
+            }
+        );
+        assert_eq!(
+            reason("a1a4"),
+            IllegalMove::PathBlocked {
+                square: square("a2")
+            }
+        );
+        assert_eq!(
+            reason("c1f4"),
+            IllegalMove::PathBlocked {
+                square: square("d2")
+            }
+        );
+        assert_eq!(
+            reason("e2d3"),
+            IllegalMove::CannotMoveThatWay {
+                piece: PieceType::Pawn,
+                from: square("e2"),
+                to: square("d3")
+            }
+        );
+        assert_eq!(reason("e2"), IllegalMove::Unreadable);
+        assert_eq!(reason("e2e4xx"), IllegalMove::Unreadable);
+
+        let blocked_pawn = "4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1";
+        assert_eq!(
+            check(blocked_pawn, "e2e4"),
+            Err(IllegalMove::PathBlocked {
+                square: square("e3")
+            })
+        );
+        assert_eq!(
+            reason("e1e2").to_string(),
+            "e2 is occupied by your own piece"
+        );
+    }
+
+    #[test]
+    fn test_king_safety() {
+        // The e2 knight is pinned, and the king can't step onto the d-file
+        let pinned = "3rr2k/8/8/8/8/8/4N3/4K3 w - - 0 1";
+        assert_eq!(check(pinned, "e2c3"), Err(IllegalMove::LeavesKingInCheck));
+        assert_eq!(check(pinned, "e1d1"), Err(IllegalMove::LeavesKingInCheck));
+        assert!(check(pinned, "e1f1").is_ok());
+    }
+
+    #[test]
+    fn test_castling_reasons() {
+        let no_rights = "r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1";
+        assert_eq!(
+            check(no_rights, "e1g1"),
+            Err(IllegalMove::CastlingRightLost { kingside: true })
+        );
+        assert!(check(no_rights, "e1c1").is_ok());
+
+        let in_check = "r3k2r/8/8/8/8/8/8/R3K2r w Qkq - 0 1";
+        assert_eq!(check(in_check, "e1c1"), Err(IllegalMove::CastlingInCheck));
+
+        // The f1 square is covered by the bishop on c4
+        let through = "r3k2r/8/8/8/2b5/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(
+            check(through, "e1g1"),
+            Err(IllegalMove::CastlingThroughCheck {
+                square: square("f1")
+            })
+        );
+        let blocked = "r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1";
+        assert_eq!(
+            check(blocked, "e1c1"),
+            Err(IllegalMove::PathBlocked {
+                square: square("b1")
+            })
+        );
+        assert_eq!(
+            IllegalMove::CastlingThroughCheck {
+                square: square("f1")
+            }
+            .to_string(),
+            "you cannot castle through or into check, f1 is attacked"
+        );
+    }
+
+    #[test]
+    fn test_promotion_reasons() {
+        let fen = "7k/P7/8/8/8/8/8/K7 w - - 0 1";
+        assert_eq!(check(fen, "a7a8"), Err(IllegalMove::MissingPromotion));
+        assert_eq!(
+            check(fen, "a7a8k"),
+            Err(IllegalMove::InvalidPromotion {
+                piece: PieceType::King
+            })
+        );
+        assert_eq!(
+            check(fen, "a7a8p"),
+            Err(IllegalMove::InvalidPromotion {
+                piece: PieceType::Pawn
+            })
+        );
+
+        // A piece named for a move that doesn't promote
+        for (uci, piece) in [
+            ("e2e4q", PieceType::Queen),
+            ("g1f3q", PieceType::Queen),
+            ("e2e3n", PieceType::Knight),
+        ] {
+            assert_eq!(
+                validate_uci(&Game::new(), uci),
+                Err(IllegalMove::InvalidPromotion { piece })
+            );
+        }
+        assert_eq!(
+            IllegalMove::InvalidPromotion {
+                piece: PieceType::Queen
+            }
+            .to_string(),
+            "this move cannot promote to a queen"
+        );
+    }
+
+    #[test]
+    fn test_antichess_reasons() {
+        let antichess = |fen: &str| Game::from_variant_fen(fen, Variant::Antichess).unwrap();
+        let game = antichess("rnbqkbnr/p1pppppp/8/1p6/8/4P3/PPPP1PPP/RNBQKBNR w - b6 0 2");
+        assert_eq!(
+            validate_uci(&game, "f1b5"),
+            Ok(Move::capture(square("f1"), square("b5")))
+        );
+        assert_eq!(
+            validate_uci(&game, "d2d4"),
+            Err(IllegalMove::CaptureRequired)
+        );
+
+        // The king may walk into attack and a pawn may become one, but there
+        // is no castling
+        let game = antichess("8/8/8/8/8/8/r7/4K2R w - - 0 1");
+        assert!(validate_uci(&game, "e1e2").is_ok());
+        assert_eq!(
+            validate_uci(&game, "e1g1"),
+            Err(IllegalMove::CannotMoveThatWay {
+                piece: PieceType::King,
+                from: square("e1"),
+                to: square("g1")
+            })
+        );
+        let game = antichess("8/P7/8/8/8/8/8/k7 w - - 0 1");
+        assert!(validate_uci(&game, "a7a8k").is_ok());
+    }
+}
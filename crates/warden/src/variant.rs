@@ -0,0 +1,278 @@
+use arena::{
+    board::LIGHT_SQUARES,
+    game::Game,
+    piece::{Color, PieceType},
+    variant::{CHECKS_TO_WIN, HILL, Variant},
+};
+use prophet::movegen::generate_legal;
+
+use crate::{
+    dead::is_dead_position,
+    outcome::{Outcome, Termination, has_insufficient_material, has_mating_material},
+};
+
+// How games of one variant are won, lost and drawn. The moves themselves come
+// from prophet, which follows the variant set on the game.
+pub trait RuleSet: Sync {
+    fn variant(&self) -> Variant;
+
+    // How the game stands after the moves played so far, None while it goes
+    // on. Only endings that need no claim count.
+    fn outcome(&self, game: &Game) -> Option<Outcome>;
+
+    // Whether `color` could still win by some series of legal moves, as
+    // decides a game lost on time
+    fn can_win(&self, game: &Game, color: Color) -> bool;
+}
+
+pub struct Standard;
+pub struct KingOfTheHill;
+pub struct ThreeCheck;
+pub struct Antichess;
+
+// The rule set for the variant the game is played by
+pub fn rules(variant: Variant) -> &'static dyn RuleSet {
+    match variant {
+        Variant::Standard => &Standard,
+        Variant::KingOfTheHill => &KingOfTheHill,
+        Variant::ThreeCheck => &ThreeCheck,
+        Variant::Antichess => &Antichess,
+    }
+}
+
+// No moves left while the king is on the board: mate or stalemate
+fn mate_or_stalemate(game: &Game) -> Option<Outcome> {
+    generate_legal(game).is_empty().then(|| {
+        if game.is_in_check() {
+            Outcome::win(game.side().opposite(), Termination::Checkmate)
+        } else {
+            Outcome::draw(Termination::Stalemate)
+        }
+    })
+}
+
+// The seventy-five move rule and fivefold repetition hold in every variant
+fn automatic_draw(game: &Game) -> Option<Outcome> {
+    if game.halfmove_clock >= 150 {
+        Some(Outcome::draw(Termination::SeventyFiveMoves))
+    } else if game.repetitions() >= 4 {
+        Some(Outcome::draw(Termination::FivefoldRepetition))
+    } else {
+        None
+    }
+}
+
+fn bare_king(game: &Game, color: Color) -> bool {
+    game.color_pieces(color) == game.pieces(PieceType::King, color)
+}
+
+impl RuleSet for Standard {
+    fn variant(&self) -> Variant {
+        Variant::Standard
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        // Mate on the move that would also hit a move limit still wins
+        if let Some(outcome) = mate_or_stalemate(game) {
+            return Some(outcome);
+        }
+        if has_insufficient_material(game) {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+        if is_dead_position(game) {
+            return Some(Outcome::draw(Termination::DeadPosition));
+        }
+        automatic_draw(game)
+    }
+
+    fn can_win(&self, game: &Game, color: Color) -> bool {
+        has_mating_material(game, color)
+    }
+}
+
+impl RuleSet for KingOfTheHill {
+    fn variant(&self) -> Variant {
+        Variant::KingOfTheHill
+    }
+
+    // A lone king can still walk to the center, so material never runs short
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        for color in [Color::White, Color::Black] {
+            if game.pieces(PieceType::King, color) & HILL != 0 {
+                return Some(Outcome::win(color, Termination::KingOfTheHill));
+            }
+        }
+        mate_or_stalemate(game).or_else(|| automatic_draw(game))
+    }
+
+    fn can_win(&self, _game: &Game, _color: Color) -> bool {
+        true
+    }
+}
+
+impl RuleSet for ThreeCheck {
+    fn variant(&self) -> Variant {
+        Variant::ThreeCheck
+    }
+
+    // Any piece can give check, so only bare kings are short of material
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        for color in [Color::White, Color::Black] {
+            if game.checks_given(color) >= CHECKS_TO_WIN {
+                return Some(Outcome::win(color, Termination::ThreeChecks));
+            }
+        }
+        if let Some(outcome) = mate_or_stalemate(game) {
+            return Some(outcome);
+        }
+        if bare_king(game, Color::White) && bare_king(game, Color::Black) {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+        automatic_draw(game)
+    }
+
+    fn can_win(&self, game: &Game, color: Color) -> bool {
+        !bare_king(game, color)
+    }
+}
+
+impl RuleSet for Antichess {
+    fn variant(&self) -> Variant {
+        Variant::Antichess
+    }
+
+    // The side left without a move wins, whether it lost every piece or is
+    // stalemated
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        let us = game.side();
+        if game.color_pieces(us) == 0 {
+            return Some(Outcome::win(us, Termination::AllPiecesLost));
+        }
+        if generate_legal(game).is_empty() {
+            return Some(Outcome::win(us, Termination::Stalemate));
+        }
+        if opposite_bishops_only(game) {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+        automatic_draw(game)
+    }
+
+    // Giving everything away is always possible in principle
+    fn can_win(&self, _game: &Game, _color: Color) -> bool {
+        true
+    }
+}
+
+// Nothing but bishops, each side's on one square color and the two sides on
+// different ones, so neither can ever be made to capture
+fn opposite_bishops_only(game: &Game) -> bool {
+    let [white, black] = [Color::White, Color::Black].map(|color| {
+        let bishops = game.pieces(PieceType::Bishop, color);
+        (bishops == game.color_pieces(color)).then_some(bishops)
+    });
+    let (Some(white), Some(black)) = (white, black) else {
+        return false;
+    };
+    (white & !LIGHT_SQUARES == 0 && black & LIGHT_SQUARES == 0)
+        || (white & LIGHT_SQUARES == 0 && black & !LIGHT_SQUARES == 0)
+}
+
+#[cfg(test)]
+mod variant_test {
+    use arena::{board::square, moves::Move};
+
+    use super::*;
+    use crate::outcome::outcome;
+
+    fn outcome_of(fen: &str, variant: Variant) -> Option<Outcome> {
+        outcome(&Game::from_variant_fen(fen, variant).unwrap())
+    }
+
+    #[test]
+    fn test_rule_sets_match_their_variant() {
+        for variant in Variant::ALL {
+            assert_eq!(rules(variant).variant(), variant);
+            assert_eq!(outcome(&Game::new_variant(variant)), None);
+        }
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        // Kd4 wins, even with the bare kings that would be a draw in chess
+        let mut game =
+            Game::from_variant_fen("8/8/8/8/8/2K5/8/7k w - - 0 1", Variant::KingOfTheHill).unwrap();
+        assert_eq!(outcome(&game), None);
+        game.make_move(Move::quiet(square("c3"), square("d4")));
+        assert_eq!(
+            outcome(&game),
+            Some(Outcome::win(Color::White, Termination::KingOfTheHill))
+        );
+        assert!(generate_legal(&game).is_empty());
+        assert!(rules(Variant::KingOfTheHill).can_win(&game, Color::Black));
+
+        // Mate still ends the game
+        let mate = outcome_of("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", Variant::KingOfTheHill);
+        assert_eq!(
+            mate,
+            Some(Outcome::win(Color::White, Termination::Checkmate))
+        );
+    }
+
+    #[test]
+    fn test_three_check() {
+        // White has given two checks, Bb5+ is the third
+        let mut game = Game::from_variant_fen(
+            "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3 +2+0",
+            Variant::ThreeCheck,
+        )
+        .unwrap();
+        assert_eq!(outcome(&game), None);
+        game.make_move(Move::quiet(square("f1"), square("b5")));
+        assert_eq!(
+            outcome(&game),
+            Some(Outcome::win(Color::White, Termination::ThreeChecks))
+        );
+        assert!(generate_legal(&game).is_empty());
+
+        // A lone knight can still check, bare kings can't
+        let fen = "8/8/4k3/8/8/3KN3/8/8 w - - 0 1";
+        assert_eq!(outcome_of(fen, Variant::ThreeCheck), None);
+        let game = Game::from_variant_fen(fen, Variant::ThreeCheck).unwrap();
+        assert!(rules(Variant::ThreeCheck).can_win(&game, Color::White));
+        assert!(!rules(Variant::ThreeCheck).can_win(&game, Color::Black));
+        assert_eq!(
+            outcome_of("8/8/4k3/8/8/3K4/8/8 w - - 0 1", Variant::ThreeCheck),
+            Some(Outcome::draw(Termination::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn test_antichess() {
+        // Black gave everything away
+        let lost_all = outcome_of("8/8/8/8/8/8/8/4K3 b - - 0 1", Variant::Antichess);
+        assert_eq!(
+            lost_all,
+            Some(Outcome::win(Color::Black, Termination::AllPiecesLost))
+        );
+        // A blocked pawn can't move, which wins too
+        let blocked = outcome_of("8/8/8/8/8/p7/P7/8 b - - 0 1", Variant::Antichess);
+        assert_eq!(
+            blocked,
+            Some(Outcome::win(Color::Black, Termination::Stalemate))
+        );
+        // Being "mated" means nothing
+        assert_eq!(
+            outcome_of("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", Variant::Antichess),
+            None
+        );
+        // Bishops on opposite colors never meet
+        assert_eq!(
+            outcome_of("8/8/8/8/8/8/b7/B7 w - - 0 1", Variant::Antichess),
+            Some(Outcome::draw(Termination::InsufficientMaterial))
+        );
+        assert_eq!(
+            outcome_of("8/8/8/8/8/8/1b6/B7 w - - 0 1", Variant::Antichess),
+            None
+        );
+    }
+}